@@ -16,7 +16,7 @@ pub fn compare_sizes(c: &mut Criterion) {
                 },
                 |(mut gen, mut a, mut b)| {
                     (0..10).for_each(|_| {
-                        gen.generate(&mut a, &mut b);
+                        gen.generate(&mut a, &mut b).unwrap();
                     });
                     black_box((a, b));
                 },
@@ -44,7 +44,7 @@ pub fn compare_aspect_ratio(c: &mut Criterion) {
                     },
                     |(mut gen, mut a, mut b)| {
                         (0..10).for_each(|_| {
-                            gen.generate(&mut a, &mut b);
+                            gen.generate(&mut a, &mut b).unwrap();
                         });
                         black_box((a, b));
                     },
@@ -1,37 +1,21 @@
 extern crate cast;
 extern crate dungenon;
-extern crate image;
 extern crate rand;
 
 use std::io;
-use std::path::PathBuf;
 
 use dungenon::generator::FactionGen;
 use dungenon::level::GridLevel as Level;
+use dungenon::level::PngLayout;
 use dungenon::tile::Faction;
-use dungenon::util::Error;
 
 use rand::distributions::{IndependentSample, Range};
 use rand::os::OsRng;
 use rand::Rand;
 use rand::XorShiftRng;
 
-use image::Rgb;
-use image::RgbImage;
-
-use std::collections::HashMap;
-
 fn main() {
-    let mut colors = HashMap::new();
-    let mut index = 0;
-    for r in 0..256 {
-        for g in 0..256 {
-            for b in 0..256 {
-                colors.insert(Faction::Faction(index), Rgb([r as u8, g as u8, b as u8]));
-                index += 1;
-            }
-        }
-    }
+    let index = 256 * 256 * 256;
     println!("Initializing level...");
     println!("Input level width: ");
     let x = usize_from_cmd();
@@ -46,10 +30,12 @@ fn main() {
     let iterations = usize_from_cmd();
     let mut buffer = level.clone();
     for i in 0..iterations {
-        factiongen.generate(&mut level, &mut buffer);
+        factiongen.generate(&mut level, &mut buffer).unwrap();
         println!("{} iterations done.", i);
     }
-    faction_png_export(String::from("picture"), &colors, &mut level);
+    PngLayout::new()
+        .save(&level, &faction_to_color, "picture.png")
+        .expect("Something went wrong when saving the png.");
 }
 
 fn populate_level(level: &mut Level<Faction>, rangemax: usize) {
@@ -67,38 +53,11 @@ fn populate_level(level: &mut Level<Faction>, rangemax: usize) {
     }
 }
 
-fn faction_png_export(
-    name: String,
-    colors: &HashMap<Faction, Rgb<u8>>,
-    level: &mut Level<Faction>,
-) {
-    let mut level_image = RgbImage::new(level.get_width() as u32, level.get_height() as u32);
-    for x in 0..level.get_width() {
-        for y in 0..level.get_height() {
-            level_image.put_pixel(
-                x as u32,
-                y as u32,
-                faction_to_color(colors, &level.get_tile(x, y)),
-            );
-        }
-    }
-    let mut p = PathBuf::new();
-    p.push(name);
-    p.set_extension("png");
-    level_image
-        .save(p.as_path())
-        .expect("Something went wrong when saving the png.");
-}
-
-fn faction_to_color(colors: &HashMap<Faction, Rgb<u8>>, tile: &Result<&Faction, Error>) -> Rgb<u8> {
-    match *tile {
-        Ok(ref tile) => match colors.get(tile) {
-            Some(color) => color.clone(),
-            None => unreachable!("aaaaa!!!"),
-        },
-        Err(Error::IndexOutOfBounds) => {
-            panic!("Tile png import failed. Level indexing went out of bounds.")
-        }
+// Every faction index below 256^3 gets its own color.
+fn faction_to_color(faction: &Faction) -> [u8; 3] {
+    match *faction {
+        Faction::Faction(index) => [(index >> 16) as u8, (index >> 8) as u8, index as u8],
+        _ => unreachable!("aaaaa!!!"),
     }
 }
 
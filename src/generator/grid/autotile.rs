@@ -0,0 +1,184 @@
+use rand::RngCore;
+
+use generator::GridGenerator;
+use level::{add_isize_to_usize, GridLevel};
+use tile::Tile;
+use util::Error;
+
+/// The neighbours an autotile index is computed from.
+///
+/// Bits are set for neighbours that connect to the tile, starting with the lowest bit for the
+/// neighbour at y - 1 and going clockwise when y = 0 is drawn on top.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AutotileMask {
+    /// The four orthogonal neighbours, giving 16 indices.
+    Four,
+    /// All eight neighbours, giving 256 indices.
+    Eight,
+    /// All eight neighbours, where a diagonal neighbour only counts if both orthogonal
+    /// neighbours next to it connect too. The 47 masks that are left are numbered in
+    /// ascending order of their 8-bit value.
+    Blob,
+}
+
+// Offsets of the neighbours in bit order: N, NE, E, SE, S, SW, W, NW.
+const NEIGHBOURS: [(isize, isize); 8] = [
+    (0, -1),
+    (1, -1),
+    (1, 0),
+    (1, 1),
+    (0, 1),
+    (-1, 1),
+    (-1, 0),
+    (-1, -1),
+];
+
+impl AutotileMask {
+    /// The number of different indices.
+    pub fn count(self) -> usize {
+        match self {
+            AutotileMask::Four => 16,
+            AutotileMask::Eight => 256,
+            AutotileMask::Blob => 47,
+        }
+    }
+}
+
+/// Computes the autotile index of the tile at `(x, y)`, or `None` for void tiles and positions
+/// outside the level. Walls connect to walls and doors, every other tile connects to passable
+/// tiles. Neighbours outside the level always connect, so walls along the edge of a level
+/// look continuous.
+pub fn autotile_index(
+    level: &GridLevel<Tile>,
+    x: usize,
+    y: usize,
+    mask: AutotileMask,
+) -> Option<usize> {
+    let connects: fn(&Tile) -> bool = match *level.get_tile(x, y).ok()? {
+        Tile::Void(_) => return None,
+        Tile::Wall(_) => |tile| matches!(*tile, Tile::Wall(_) | Tile::Door(_)),
+        _ => Tile::is_passable,
+    };
+    let mut bits = 0;
+    for (bit, &(dx, dy)) in NEIGHBOURS.iter().enumerate() {
+        let neighbour = match (add_isize_to_usize(dx, x), add_isize_to_usize(dy, y)) {
+            (Some(nx), Some(ny)) => level.get_tile(nx, ny).ok(),
+            _ => None,
+        };
+        if neighbour.map_or(true, connects) {
+            bits |= 1 << bit;
+        }
+    }
+    Some(match mask {
+        AutotileMask::Four => (bits & 1) | (bits >> 1 & 2) | (bits >> 2 & 4) | (bits >> 3 & 8),
+        AutotileMask::Eight => bits,
+        AutotileMask::Blob => {
+            let reduced = reduce_corners(bits);
+            (0..reduced).filter(|&m| reduce_corners(m) == m).count()
+        }
+    })
+}
+
+/// Clears the diagonal bits whose two orthogonal neighbours aren't both set.
+fn reduce_corners(bits: usize) -> usize {
+    let mut reduced = bits & 0b0101_0101;
+    for corner in &[1, 3, 5, 7] {
+        let (before, after) = (corner - 1, (corner + 1) % 8);
+        if bits >> before & 1 == 1 && bits >> after & 1 == 1 {
+            reduced |= bits & 1 << corner;
+        }
+    }
+    reduced
+}
+
+/// Writes autotile indices into the payload of walls and, optionally, floors.
+///
+/// The index of every tile is computed before any tile is changed and is looked up in the
+/// tile's mapping table, which defaults to the index itself. Doors, stairs and void tiles keep
+/// their payload.
+pub struct Autotiler {
+    mask: AutotileMask,
+    walls: Option<Vec<usize>>,
+    floors: Option<Vec<usize>>,
+}
+
+impl Autotiler {
+    /// Creates an `Autotiler` that sets the payload of walls to their index.
+    pub fn new(mask: AutotileMask) -> Autotiler {
+        Autotiler {
+            mask,
+            walls: Some((0..mask.count()).collect()),
+            floors: None,
+        }
+    }
+
+    /// Sets the payload of a wall with index `i` to `table[i]`. `table` needs an entry for
+    /// every index of the mask.
+    pub fn with_wall_table(mut self, table: Vec<usize>) -> Autotiler {
+        self.walls = Some(table);
+        self
+    }
+
+    /// Also sets the payload of floors, to `table[i]` for a floor with index `i`.
+    pub fn with_floor_table(mut self, table: Vec<usize>) -> Autotiler {
+        self.floors = Some(table);
+        self
+    }
+
+    /// Leaves the payload of walls alone.
+    pub fn without_walls(mut self) -> Autotiler {
+        self.walls = None;
+        self
+    }
+
+    /// Fails without changing the level if a mapping table is too short for the mask.
+    pub fn autotile(&self, level: &mut GridLevel<Tile>) -> Result<(), Error> {
+        for (name, table) in &[("wall", &self.walls), ("floor", &self.floors)] {
+            if let Some(ref table) = **table {
+                if table.len() < self.mask.count() {
+                    return Err(Error::InvalidConfig(format!(
+                        "Autotiler: the {} table has {} entries but the mask has {} indices",
+                        name,
+                        table.len(),
+                        self.mask.count()
+                    )));
+                }
+            }
+        }
+        let (width, height) = (level.get_width(), level.get_height());
+        let mut indices = Vec::with_capacity(width * height);
+        for y in 0..height {
+            for x in 0..width {
+                indices.push(autotile_index(level, x, y, self.mask));
+            }
+        }
+        for y in 0..height {
+            for x in 0..width {
+                let index = match indices[x + y * width] {
+                    Some(index) => index,
+                    None => continue,
+                };
+                match level.tiles[(x, y)] {
+                    Tile::Wall(ref mut payload) => {
+                        if let Some(ref table) = self.walls {
+                            *payload = table[index];
+                        }
+                    }
+                    Tile::Floor(ref mut payload) => {
+                        if let Some(ref table) = self.floors {
+                            *payload = table[index];
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl GridGenerator<Tile> for Autotiler {
+    fn generate(&mut self, level: &mut GridLevel<Tile>, _: &mut dyn RngCore) -> Result<(), Error> {
+        self.autotile(level)
+    }
+}
@@ -0,0 +1,255 @@
+use std::cmp;
+
+use Point;
+
+use rand::{
+    distributions::{Distribution, Uniform},
+    rngs::OsRng,
+    seq::SliceRandom,
+    Rng, RngCore, SeedableRng,
+};
+use rand_xorshift::XorShiftRng;
+
+use generator::grid::room::Room;
+use generator::GridGenerator;
+use level::GridLevel;
+use tile::Tile;
+use util::Error;
+
+/// Generates rooms by binary space partitioning.
+///
+/// The level is split recursively until the leaves can't be split any further without getting
+/// smaller than `min_leaf_size`. Every leaf gets exactly one room, so the whole level is covered
+/// evenly. The rooms of sibling leaves are then connected by L-shaped corridors, bottom up,
+/// which makes every room reachable.
+pub struct BspGen {
+    min_leaf_size: usize,
+    split_ratio: f64,
+    room_padding: usize,
+    corridor_width: usize,
+    rooms: Vec<Room>,
+    rand: XorShiftRng,
+}
+
+/// A rectangular part of the level. `x` and `y` are the corner with the smallest coordinates.
+#[derive(Clone, Copy)]
+struct Leaf {
+    x: usize,
+    y: usize,
+    width: usize,
+    height: usize,
+}
+
+impl BspGen {
+    /// Creates a `BspGen` that splits the level into leaves of at least `min_leaf_size` tiles
+    /// per side. Splits fall between 35% and 65% of a leaf, rooms keep one tile of padding to
+    /// their leaf's edges and corridors are one tile wide.
+    pub fn new(min_leaf_size: usize) -> BspGen {
+        BspGen::from_rng(min_leaf_size, &mut OsRng::new().unwrap())
+    }
+
+    pub fn with_seed(min_leaf_size: usize, seed: u64) -> BspGen {
+        BspGen::from_rng(min_leaf_size, &mut XorShiftRng::seed_from_u64(seed))
+    }
+
+    pub fn from_rng<R: Rng>(min_leaf_size: usize, rng: &mut R) -> BspGen {
+        BspGen {
+            min_leaf_size: cmp::max(min_leaf_size, 1),
+            split_ratio: 0.35,
+            room_padding: 1,
+            corridor_width: 1,
+            rooms: Vec::new(),
+            rand: XorShiftRng::from_rng(rng).unwrap(),
+        }
+    }
+
+    /// Splits are placed between `split_ratio` and `1 - split_ratio` of a leaf's side,
+    /// as far as `min_leaf_size` allows. Values are clamped to [0, 0.5].
+    pub fn with_split_ratio(mut self, split_ratio: f64) -> BspGen {
+        self.split_ratio = split_ratio.clamp(0., 0.5);
+        self
+    }
+
+    /// Sets the number of wall tiles kept between a room and the edges of its leaf.
+    /// The padding shrinks for leaves that are too small for it.
+    pub fn with_room_padding(mut self, room_padding: usize) -> BspGen {
+        self.room_padding = room_padding;
+        self
+    }
+
+    pub fn with_corridor_width(mut self, corridor_width: usize) -> BspGen {
+        self.corridor_width = cmp::max(corridor_width, 1);
+        self
+    }
+
+    pub fn reseed(&mut self, seed: u64) {
+        self.rand = XorShiftRng::seed_from_u64(seed);
+    }
+
+    /// The rooms placed by the last call to `generate`, one per leaf. A room's id is its
+    /// index here.
+    pub fn rooms(&self) -> &[Room] {
+        &self.rooms
+    }
+
+    pub fn generate(&mut self, level: &mut GridLevel<Tile>) {
+        let mut rand = self.rand.clone();
+        self.generate_with(level, &mut rand);
+        self.rand = rand;
+    }
+
+    fn generate_with<R: Rng + ?Sized>(&mut self, level: &mut GridLevel<Tile>, rng: &mut R) {
+        self.rooms = Vec::new();
+        if level.get_width() == 0 || level.get_height() == 0 {
+            return;
+        }
+        let root = Leaf {
+            x: 0,
+            y: 0,
+            width: level.get_width(),
+            height: level.get_height(),
+        };
+        self.build(root, level, rng);
+    }
+
+    /// Splits `leaf` or places a room in it. Returns the ids of all rooms below `leaf`.
+    fn build<R: Rng + ?Sized>(
+        &mut self,
+        leaf: Leaf,
+        level: &mut GridLevel<Tile>,
+        rng: &mut R,
+    ) -> Vec<usize> {
+        match self.split(leaf, rng) {
+            Some((a, b)) => {
+                let mut rooms = self.build(a, level, rng);
+                let others = self.build(b, level, rng);
+                let from = self.rooms[*rooms.choose(rng).unwrap()].center();
+                let to = self.rooms[*others.choose(rng).unwrap()].center();
+                self.dig_corridor(level, from, to, rng.gen());
+                rooms.extend(others);
+                rooms
+            }
+            None => {
+                let id = self.rooms.len();
+                let room = self.place_room(leaf, id, rng);
+                carve(level, room.min, room.max);
+                self.rooms.push(room);
+                vec![id]
+            }
+        }
+    }
+
+    fn split<R: Rng + ?Sized>(&self, leaf: Leaf, rng: &mut R) -> Option<(Leaf, Leaf)> {
+        let can_split = |size: usize| size >= 2 * self.min_leaf_size;
+        let vertical = match (can_split(leaf.width), can_split(leaf.height)) {
+            (false, false) => return None,
+            (true, false) => true,
+            (false, true) => false,
+            // Prefer cutting the longer side so that leaves don't get too narrow.
+            (true, true) if leaf.width * 4 > leaf.height * 5 => true,
+            (true, true) if leaf.height * 4 > leaf.width * 5 => false,
+            (true, true) => rng.gen(),
+        };
+        let size = if vertical { leaf.width } else { leaf.height };
+        let low = cmp::max(
+            (size as f64 * self.split_ratio) as usize,
+            self.min_leaf_size,
+        );
+        let high = cmp::min(
+            (size as f64 * (1. - self.split_ratio)).ceil() as usize,
+            size - self.min_leaf_size,
+        );
+        let at = if low < high {
+            Uniform::new_inclusive(low as u64, high as u64).sample(rng) as usize
+        } else {
+            cmp::min(low, size - self.min_leaf_size)
+        };
+
+        Some(if vertical {
+            (
+                Leaf { width: at, ..leaf },
+                Leaf {
+                    x: leaf.x + at,
+                    width: leaf.width - at,
+                    ..leaf
+                },
+            )
+        } else {
+            (
+                Leaf { height: at, ..leaf },
+                Leaf {
+                    y: leaf.y + at,
+                    height: leaf.height - at,
+                    ..leaf
+                },
+            )
+        })
+    }
+
+    fn place_room<R: Rng + ?Sized>(&self, leaf: Leaf, id: usize, rng: &mut R) -> Room {
+        let mut span = |start: usize, size: usize| {
+            let padding = cmp::min(self.room_padding, (size - 1) / 2);
+            let space = size - 2 * padding;
+            let len = Uniform::new_inclusive(cmp::max(space / 2, 1) as u64, space as u64)
+                .sample(rng) as usize;
+            let offset = Uniform::new_inclusive(0, (space - len) as u64).sample(rng) as usize;
+            let min = start + padding + offset;
+            (min, min + len - 1)
+        };
+        let (min_x, max_x) = span(leaf.x, leaf.width);
+        let (min_y, max_y) = span(leaf.y, leaf.height);
+        Room {
+            id,
+            min: Point::new(min_x, min_y),
+            max: Point::new(max_x, max_y),
+        }
+    }
+
+    /// Digs an L-shaped corridor from `from` to `to`, going along x first if `x_first` is set.
+    fn dig_corridor(
+        &self,
+        level: &mut GridLevel<Tile>,
+        from: Point<usize>,
+        to: Point<usize>,
+        x_first: bool,
+    ) {
+        let corner = if x_first {
+            Point::new(to.x, from.y)
+        } else {
+            Point::new(from.x, to.y)
+        };
+        for &(a, b) in &[(from, corner), (corner, to)] {
+            // The corridor is centered on the line between the points.
+            let back = (self.corridor_width - 1) / 2;
+            let forward = self.corridor_width - 1 - back;
+            let min = Point::new(
+                cmp::min(a.x, b.x).saturating_sub(back),
+                cmp::min(a.y, b.y).saturating_sub(back),
+            );
+            let max = Point::new(cmp::max(a.x, b.x) + forward, cmp::max(a.y, b.y) + forward);
+            carve(level, min, max);
+        }
+    }
+}
+
+impl GridGenerator<Tile> for BspGen {
+    fn generate(
+        &mut self,
+        level: &mut GridLevel<Tile>,
+        rng: &mut dyn RngCore,
+    ) -> Result<(), Error> {
+        self.generate_with(level, rng);
+        Ok(())
+    }
+}
+
+/// Turns every tile in the rectangle between `min` and `max`, inclusive, into floor.
+fn carve(level: &mut GridLevel<Tile>, min: Point<usize>, max: Point<usize>) {
+    for y in min.y..=max.y {
+        for x in min.x..=max.x {
+            if let Ok(tile) = level.get_mut_tile(x, y) {
+                *tile = Tile::Floor(0);
+            }
+        }
+    }
+}
@@ -0,0 +1,240 @@
+use std::collections::VecDeque;
+
+use rand::{rngs::OsRng, Rng, RngCore, SeedableRng};
+use rand_xorshift::XorShiftRng;
+
+use generator::GridGenerator;
+use level::GridLevel;
+use tile::Tile;
+use util::{Error, Grid, Neighbourhood};
+
+/// Birth and survival rules for `CaveGen`, indexed by the number of neighbouring walls.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CaveRules {
+    birth: [bool; 9],
+    survival: [bool; 9],
+}
+
+impl CaveRules {
+    /// A floor tile becomes a wall if it has a number of wall neighbours listed in `birth`,
+    /// a wall stays a wall if its number of wall neighbours is listed in `survival`.
+    /// Counts above 8 are ignored.
+    pub fn new(birth: &[usize], survival: &[usize]) -> CaveRules {
+        let mut rules = CaveRules {
+            birth: [false; 9],
+            survival: [false; 9],
+        };
+        for &n in birth.iter().filter(|&&n| n <= 8) {
+            rules.birth[n] = true;
+        }
+        for &n in survival.iter().filter(|&&n| n <= 8) {
+            rules.survival[n] = true;
+        }
+        rules
+    }
+
+    fn is_wall(&self, wall: bool, walls: usize) -> bool {
+        if wall {
+            self.survival[walls]
+        } else {
+            self.birth[walls]
+        }
+    }
+}
+
+/// The common B5678/S45678 rule, which smooths random noise into open caverns.
+impl Default for CaveRules {
+    fn default() -> CaveRules {
+        CaveRules::new(&[5, 6, 7, 8], &[4, 5, 6, 7, 8])
+    }
+}
+
+/// Generates organic caves with a cellular automaton.
+///
+/// The level is filled with walls at random and then smoothed by applying the rules
+/// `iterations` times. Tiles outside the level count as walls and the outermost tiles
+/// always stay walls, so caverns never touch the level's edge. `CaveGen` overwrites the
+/// whole level.
+pub struct CaveGen {
+    fill_ratio: f64,
+    rules: CaveRules,
+    iterations: usize,
+    neighbourhood: Neighbourhood,
+    largest_only: bool,
+    rand: XorShiftRng,
+}
+
+impl CaveGen {
+    /// Creates a `CaveGen` that fills 45% of the level with walls, runs 4 iterations of the
+    /// default rules with the 8-neighbourhood and keeps every cavern.
+    pub fn new() -> CaveGen {
+        CaveGen::from_rng(&mut OsRng::new().unwrap())
+    }
+
+    pub fn with_seed(seed: u64) -> CaveGen {
+        CaveGen::from_rng(&mut XorShiftRng::seed_from_u64(seed))
+    }
+
+    pub fn from_rng<R: Rng>(rng: &mut R) -> CaveGen {
+        CaveGen {
+            fill_ratio: 0.45,
+            rules: CaveRules::default(),
+            iterations: 4,
+            neighbourhood: Neighbourhood::Eight,
+            largest_only: false,
+            rand: XorShiftRng::from_rng(rng).unwrap(),
+        }
+    }
+
+    /// Sets the chance for each tile to start out as a wall.
+    pub fn with_fill_ratio(mut self, fill_ratio: f64) -> CaveGen {
+        self.fill_ratio = fill_ratio;
+        self
+    }
+
+    pub fn with_rules(mut self, rules: CaveRules) -> CaveGen {
+        self.rules = rules;
+        self
+    }
+
+    pub fn with_iterations(mut self, iterations: usize) -> CaveGen {
+        self.iterations = iterations;
+        self
+    }
+
+    /// Sets which tiles are counted as neighbours by the rules.
+    pub fn with_neighbourhood(mut self, neighbourhood: Neighbourhood) -> CaveGen {
+        self.neighbourhood = neighbourhood;
+        self
+    }
+
+    /// If set, every cavern except the largest one is filled with walls afterwards.
+    /// Caverns are connected through orthogonally adjacent floors.
+    pub fn keep_largest_cavern(mut self, largest_only: bool) -> CaveGen {
+        self.largest_only = largest_only;
+        self
+    }
+
+    pub fn reseed(&mut self, seed: u64) {
+        self.rand = XorShiftRng::seed_from_u64(seed);
+    }
+
+    pub fn generate(&mut self, level: &mut GridLevel<Tile>) {
+        let mut rand = self.rand.clone();
+        self.generate_with(level, &mut rand);
+        self.rand = rand;
+    }
+
+    fn generate_with<R: Rng + ?Sized>(&self, level: &mut GridLevel<Tile>, rng: &mut R) {
+        let width = level.get_width();
+        let height = level.get_height();
+        if width == 0 || height == 0 {
+            return;
+        }
+        let border = |x: usize, y: usize| x == 0 || y == 0 || x == width - 1 || y == height - 1;
+
+        let mut walls = Grid::new_filled_with(true, width, height);
+        for y in 0..height {
+            for x in 0..width {
+                walls[(x, y)] = border(x, y) || rng.gen::<f64>() < self.fill_ratio;
+            }
+        }
+        let mut next = walls.clone();
+        for _ in 0..self.iterations {
+            for y in 0..height {
+                for x in 0..width {
+                    next[(x, y)] = border(x, y)
+                        || self
+                            .rules
+                            .is_wall(walls[(x, y)], self.wall_count(&walls, (x, y)));
+                }
+            }
+            ::std::mem::swap(&mut walls, &mut next);
+        }
+        if self.largest_only {
+            keep_largest(&mut walls);
+        }
+
+        for y in 0..height {
+            for x in 0..width {
+                level.tiles[(x, y)] = if walls[(x, y)] {
+                    Tile::Wall(0)
+                } else {
+                    Tile::Floor(0)
+                };
+            }
+        }
+    }
+
+    fn wall_count(&self, walls: &Grid<bool>, pos: (usize, usize)) -> usize {
+        self.neighbourhood
+            .dirs()
+            .iter()
+            .filter(|dir| match dir.offset(pos) {
+                Some((x, y)) if x < walls.get_width() && y < walls.get_height() => walls[(x, y)],
+                _ => true,
+            })
+            .count()
+    }
+}
+
+impl Default for CaveGen {
+    fn default() -> CaveGen {
+        CaveGen::new()
+    }
+}
+
+impl GridGenerator<Tile> for CaveGen {
+    fn generate(
+        &mut self,
+        level: &mut GridLevel<Tile>,
+        rng: &mut dyn RngCore,
+    ) -> Result<(), Error> {
+        self.generate_with(level, rng);
+        Ok(())
+    }
+}
+
+/// Turns every floor that isn't part of the largest orthogonally connected area into a wall.
+fn keep_largest(walls: &mut Grid<bool>) {
+    let width = walls.get_width();
+    let height = walls.get_height();
+    let mut regions: Grid<Option<usize>> = Grid::new_filled_with(None, width, height);
+    let mut sizes = Vec::new();
+    let mut queue = VecDeque::new();
+    for y in 0..height {
+        for x in 0..width {
+            if walls[(x, y)] || regions[(x, y)].is_some() {
+                continue;
+            }
+            let region = sizes.len();
+            let mut size = 0;
+            regions[(x, y)] = Some(region);
+            queue.push_back((x, y));
+            while let Some(pos) = queue.pop_front() {
+                size += 1;
+                for dir in Neighbourhood::Four.dirs() {
+                    if let Some((nx, ny)) = dir.offset(pos) {
+                        if nx < width
+                            && ny < height
+                            && !walls[(nx, ny)]
+                            && regions[(nx, ny)].is_none()
+                        {
+                            regions[(nx, ny)] = Some(region);
+                            queue.push_back((nx, ny));
+                        }
+                    }
+                }
+            }
+            sizes.push(size);
+        }
+    }
+    let largest = (0..sizes.len()).max_by_key(|&region| (sizes[region], usize::MAX - region));
+    for y in 0..height {
+        for x in 0..width {
+            if regions[(x, y)].is_some() && regions[(x, y)] != largest {
+                walls[(x, y)] = true;
+            }
+        }
+    }
+}
@@ -0,0 +1,315 @@
+use std::cmp::min;
+use std::collections::VecDeque;
+
+use rand::{seq::SliceRandom, Rng};
+
+use generator::grid::room::Room;
+use level::GridLevel;
+use tile::Tile;
+use util::{Direction, Grid, UnionFind};
+
+/// Connects rooms to the corridors around them with doors.
+///
+/// Rooms are first walled off from everything they were carved over. Then doors are opened
+/// through the walls between rooms and corridors (or between two rooms) until every room is
+/// reachable. Rooms that still have fewer than `doors_per_room` doors get more of them, and
+/// every remaining wall tile between two areas becomes a door with `extra_door_chance`.
+/// Doors beyond the ones needed to reach every room create loops.
+pub struct RoomConnector {
+    doors_per_room: usize,
+    extra_door_chance: f64,
+}
+
+struct Candidate {
+    pos: (usize, usize),
+    regions: (usize, usize),
+}
+
+impl RoomConnector {
+    pub fn new(doors_per_room: usize, extra_door_chance: f64) -> RoomConnector {
+        RoomConnector {
+            doors_per_room,
+            extra_door_chance,
+        }
+    }
+
+    /// Walls off `rooms` and opens doors into them. `rooms` should be the rooms that were
+    /// carved into `level`, for example `RoomGen::rooms`.
+    pub fn connect<R: Rng + ?Sized>(
+        &self,
+        level: &mut GridLevel<Tile>,
+        rooms: &[Room],
+        rng: &mut R,
+    ) {
+        let width = level.get_width();
+        let height = level.get_height();
+        if rooms.is_empty() || width == 0 || height == 0 {
+            return;
+        }
+        let mut room_at = Grid::new_filled_with(None, width, height);
+        for (id, room) in rooms.iter().enumerate() {
+            for y in room.min.y..=min(room.max.y, height - 1) {
+                for x in room.min.x..=min(room.max.x, width - 1) {
+                    room_at[(x, y)] = Some(id);
+                }
+            }
+        }
+        seal(level, rooms, &room_at);
+
+        let (mut regions, mut sets) = label_regions(level, &room_at, rooms.len());
+        let mut candidates = find_candidates(level, &regions, rooms.len());
+        candidates.shuffle(rng);
+
+        let mut doors = Vec::new();
+        let mut door_counts = vec![0; rooms.len()];
+        let mut opened = vec![false; candidates.len()];
+        for (i, candidate) in candidates.iter().enumerate() {
+            if sets.union(candidate.regions.0, candidate.regions.1) {
+                open_door(level, &mut regions, candidate, &mut door_counts);
+                doors.push(candidate.pos);
+                opened[i] = true;
+            }
+        }
+        for (i, candidate) in candidates.iter().enumerate() {
+            if opened[i] || touches_door(&doors, candidate.pos) {
+                continue;
+            }
+            let (a, b) = candidate.regions;
+            let wants_door = [a, b]
+                .iter()
+                .any(|&r| r < rooms.len() && door_counts[r] < self.doors_per_room);
+            if wants_door || rng.gen::<f64>() < self.extra_door_chance {
+                open_door(level, &mut regions, candidate, &mut door_counts);
+                doors.push(candidate.pos);
+            }
+        }
+
+        // Rooms that had no wall in common with the rest of the level get a tunnel.
+        while let Some(room) = (1..rooms.len()).find(|&r| sets.find(r) != sets.find(0)) {
+            if !tunnel(level, &mut regions, &mut sets, &room_at, room) {
+                break;
+            }
+        }
+    }
+}
+
+impl Default for RoomConnector {
+    fn default() -> RoomConnector {
+        RoomConnector::new(1, 0.)
+    }
+}
+
+/// Turns every passable tile that borders a room from the outside into a wall.
+fn seal(level: &mut GridLevel<Tile>, rooms: &[Room], room_at: &Grid<Option<usize>>) {
+    for room in rooms {
+        let min_x = room.min.x.saturating_sub(1);
+        let min_y = room.min.y.saturating_sub(1);
+        let max_x = room.max.x + 1;
+        let max_y = room.max.y + 1;
+        for y in min_y..=max_y {
+            for x in min_x..=max_x {
+                if x != min_x && x != max_x && y != min_y && y != max_y {
+                    continue;
+                }
+                if let Ok(tile) = level.get_mut_tile(x, y) {
+                    if room_at[(x, y)].is_none() && tile.is_passable() {
+                        *tile = Tile::Wall(0);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Gives every room its own region and every other connected area of passable tiles a region
+/// after the rooms. Regions that touch each other are joined in the returned `UnionFind`.
+fn label_regions(
+    level: &GridLevel<Tile>,
+    room_at: &Grid<Option<usize>>,
+    room_count: usize,
+) -> (Grid<Option<usize>>, UnionFind) {
+    let width = level.get_width();
+    let height = level.get_height();
+    let mut regions = Grid::new_filled_with(None, width, height);
+    for y in 0..height {
+        for x in 0..width {
+            if level.tiles[(x, y)].is_passable() {
+                regions[(x, y)] = room_at[(x, y)];
+            }
+        }
+    }
+    let mut count = room_count;
+    let mut queue = VecDeque::new();
+    for y in 0..height {
+        for x in 0..width {
+            if regions[(x, y)].is_some() || !level.tiles[(x, y)].is_passable() {
+                continue;
+            }
+            regions[(x, y)] = Some(count);
+            queue.push_back((x, y));
+            while let Some(pos) = queue.pop_front() {
+                for n in neighbours(level, pos) {
+                    if regions[n].is_none() && room_at[n].is_none() && level.tiles[n].is_passable()
+                    {
+                        regions[n] = Some(count);
+                        queue.push_back(n);
+                    }
+                }
+            }
+            count += 1;
+        }
+    }
+
+    let mut sets = UnionFind::new(count);
+    for y in 0..height {
+        for x in 0..width {
+            if let Some(a) = regions[(x, y)] {
+                for n in neighbours(level, (x, y)) {
+                    if let Some(b) = regions[n] {
+                        sets.union(a, b);
+                    }
+                }
+            }
+        }
+    }
+    (regions, sets)
+}
+
+/// Finds the wall tiles that have two different regions on opposite sides,
+/// at least one of which is a room.
+fn find_candidates(
+    level: &GridLevel<Tile>,
+    regions: &Grid<Option<usize>>,
+    room_count: usize,
+) -> Vec<Candidate> {
+    use util::Direction::*;
+    let mut candidates = Vec::new();
+    for y in 0..level.get_height() {
+        for x in 0..level.get_width() {
+            match level.tiles[(x, y)] {
+                Tile::Wall(_) => {}
+                _ => continue,
+            }
+            let region = |dir: &Direction| {
+                dir.offset((x, y))
+                    .filter(|&n| level.get_tile_with_tuple(n).is_ok())
+                    .and_then(|n| regions[n])
+            };
+            for (a, b) in &[(Left, Right), (Down, Up)] {
+                if let (Some(a), Some(b)) = (region(a), region(b)) {
+                    if a != b && (a < room_count || b < room_count) {
+                        candidates.push(Candidate {
+                            pos: (x, y),
+                            regions: (a, b),
+                        });
+                        break;
+                    }
+                }
+            }
+        }
+    }
+    candidates
+}
+
+fn open_door(
+    level: &mut GridLevel<Tile>,
+    regions: &mut Grid<Option<usize>>,
+    candidate: &Candidate,
+    door_counts: &mut [usize],
+) {
+    level.tiles[candidate.pos] = Tile::Door(0);
+    regions[candidate.pos] = Some(candidate.regions.0);
+    for &region in &[candidate.regions.0, candidate.regions.1] {
+        if let Some(count) = door_counts.get_mut(region) {
+            *count += 1;
+        }
+    }
+}
+
+fn touches_door(doors: &[(usize, usize)], (x, y): (usize, usize)) -> bool {
+    doors.iter().any(|&(dx, dy)| {
+        (dx as isize - x as isize).abs() <= 1 && (dy as isize - y as isize).abs() <= 1
+    })
+}
+
+/// Carves the shortest tunnel through walls from the area containing `room` to any
+/// passable tile that isn't connected to it. Returns false if there is no such tunnel.
+fn tunnel(
+    level: &mut GridLevel<Tile>,
+    regions: &mut Grid<Option<usize>>,
+    sets: &mut UnionFind,
+    room_at: &Grid<Option<usize>>,
+    room: usize,
+) -> bool {
+    let width = level.get_width();
+    let height = level.get_height();
+    let root = sets.find(room);
+    let mut parent: Grid<Option<(usize, usize)>> = Grid::new_filled_with(None, width, height);
+    let mut visited = Grid::new_filled_with(false, width, height);
+    let mut queue = VecDeque::new();
+    for y in 0..height {
+        for x in 0..width {
+            if let Some(region) = regions[(x, y)] {
+                if sets.find(region) == root {
+                    visited[(x, y)] = true;
+                    queue.push_back((x, y));
+                }
+            }
+        }
+    }
+
+    let mut found = None;
+    'search: while let Some(pos) = queue.pop_front() {
+        for n in neighbours(level, pos) {
+            if visited[n] {
+                continue;
+            }
+            match level.tiles[n] {
+                Tile::Wall(_) => {
+                    visited[n] = true;
+                    parent[n] = Some(pos);
+                    queue.push_back(n);
+                }
+                ref tile if tile.is_passable() && parent[pos].is_some() => {
+                    found = Some((pos, n));
+                    break 'search;
+                }
+                _ => {}
+            }
+        }
+    }
+    let (end, target) = match found {
+        Some(found) => found,
+        None => return false,
+    };
+
+    let mut path = vec![end];
+    let mut start = end;
+    while let Some(prev) = parent[start] {
+        if parent[prev].is_none() {
+            break;
+        }
+        path.push(prev);
+        start = prev;
+    }
+    let source = parent[start].unwrap();
+    let source_region = regions[source];
+    for &pos in &path {
+        let door = (pos == start && room_at[source].is_some())
+            || (pos == end && room_at[target].is_some());
+        level.tiles[pos] = if door { Tile::Door(0) } else { Tile::Floor(0) };
+        regions[pos] = source_region;
+    }
+    if let (Some(a), Some(b)) = (source_region, regions[target]) {
+        sets.union(a, b);
+    }
+    true
+}
+
+fn neighbours(level: &GridLevel<Tile>, pos: (usize, usize)) -> Vec<(usize, usize)> {
+    Direction::get_orthogonal_dirs()
+        .iter()
+        .filter_map(|dir| dir.offset(pos))
+        .filter(|&n| level.get_tile_with_tuple(n).is_ok())
+        .collect()
+}
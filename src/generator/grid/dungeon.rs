@@ -1,11 +1,33 @@
-use generator::{MazeGen, RoomGen};
+use generator::grid::connector::RoomConnector;
+use generator::grid::room::Room;
+use generator::{GridGenerator, MazeGen, RoomGen};
 use level::GridLevel;
-use level::fill_dead_end_tiles;
+use level::{braid_dead_ends, fill_dead_end_tiles, is_deadend};
 use tile::Tile;
+use util::{Direction, Error};
+
+use rand::{rngs::OsRng, seq::SliceRandom, Rng, RngCore, SeedableRng};
+use rand_xorshift::XorShiftRng;
+
+/// How much of the corridors `DungeonGen` prunes away by filling dead ends.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Sparseness {
+    /// Fill dead ends until none are left.
+    Full,
+    /// Run the given number of `fill_dead_end_tiles` passes.
+    Passes(usize),
+    /// Fill dead ends until the given fraction of the corridor tiles has been removed
+    /// or no dead ends are left.
+    Fraction(f64),
+}
 
 pub struct DungeonGen {
     mazegen: MazeGen,
     roomgen: RoomGen,
+    connector: RoomConnector,
+    sparseness: Sparseness,
+    loop_chance: f64,
+    rand: XorShiftRng,
 }
 
 impl DungeonGen {
@@ -13,13 +35,150 @@ impl DungeonGen {
         DungeonGen{
             mazegen: mazegen,
             roomgen: roomgen,
+            connector: RoomConnector::default(),
+            sparseness: Sparseness::Full,
+            loop_chance: 0.,
+            rand: XorShiftRng::from_rng(&mut OsRng::new().unwrap()).unwrap(),
         }
     }
 
+    /// Creates a `DungeonGen` that reseeds the given generators from a single master seed.
+    /// The same seed and parameters always produce the same level.
+    pub fn with_seed(mazegen: MazeGen, roomgen: RoomGen, seed: u64) -> DungeonGen {
+        let mut dungeongen = DungeonGen::new(mazegen, roomgen);
+        dungeongen.reseed(seed);
+        dungeongen
+    }
+
+    /// Replaces the default connector, which gives every room one door and adds no loops.
+    pub fn with_connector(mut self, connector: RoomConnector) -> DungeonGen {
+        self.connector = connector;
+        self
+    }
+
+    /// Sets how much of the corridors is pruned. Defaults to `Sparseness::Full`.
+    pub fn with_sparseness(mut self, sparseness: Sparseness) -> DungeonGen {
+        self.sparseness = sparseness;
+        self
+    }
+
+    /// Sets the fraction of dead ends that are turned into loops with `braid_dead_ends`
+    /// before pruning. Walls knocked down into a room become doors. Defaults to 0.
+    pub fn with_loop_chance(mut self, loop_chance: f64) -> DungeonGen {
+        self.loop_chance = loop_chance;
+        self
+    }
+
+    /// The rooms placed by the room generator.
+    pub fn rooms(&self) -> &[Room] {
+        self.roomgen.rooms()
+    }
+
+    /// Derives new seeds for the maze and room generators from `seed`.
+    pub fn reseed(&mut self, seed: u64) {
+        let mut master = XorShiftRng::seed_from_u64(seed);
+        self.mazegen.reseed(master.gen());
+        self.roomgen.reseed(master.gen());
+        self.rand = XorShiftRng::seed_from_u64(master.gen());
+    }
+
     pub fn generate(&mut self, level: &mut GridLevel<Tile>) {
-        level.apply(|m| self.mazegen.generate(m))
-        .apply(|m| self.roomgen.generate(m));
+        level
+            .apply(|m| self.mazegen.generate(m))
+            .apply(|m| self.roomgen.generate(m));
 
-        while fill_dead_end_tiles(level) {}
+        let mut rand = self.rand.clone();
+        self.finish(level, &mut rand);
+        self.rand = rand;
+    }
+
+    fn finish<R: Rng + ?Sized>(&self, level: &mut GridLevel<Tile>, rng: &mut R) {
+        let rooms = self.roomgen.rooms();
+        self.connector.connect(level, rooms, rng);
+        if self.loop_chance > 0. {
+            let before = level.clone();
+            braid_dead_ends(level, self.loop_chance, rng);
+            // Walls knocked down into a room become doors.
+            for y in 0..level.get_height() {
+                for x in 0..level.get_width() {
+                    let knocked = matches!(before.tiles[(x, y)], Tile::Wall(_))
+                        && matches!(level.tiles[(x, y)], Tile::Floor(_));
+                    let into_room = Direction::get_orthogonal_dirs()
+                        .iter()
+                        .filter_map(|dir| dir.offset((x, y)))
+                        .any(|(nx, ny)| rooms.iter().any(|room| room.contains(nx, ny)));
+                    if knocked && into_room {
+                        level.tiles[(x, y)] = Tile::Door(0);
+                    }
+                }
+            }
+        }
+        match self.sparseness {
+            Sparseness::Full => while fill_dead_end_tiles(level) {},
+            Sparseness::Passes(passes) => {
+                for _ in 0..passes {
+                    if !fill_dead_end_tiles(level) {
+                        break;
+                    }
+                }
+            }
+            Sparseness::Fraction(fraction) => {
+                let corridors = corridor_tiles(level, rooms).len() as f64;
+                let limit = (corridors * fraction).round() as usize;
+                fill_dead_ends_up_to(level, rooms, limit, rng);
+            }
+        }
+    }
+}
+
+impl GridGenerator<Tile> for DungeonGen {
+    fn generate(
+        &mut self,
+        level: &mut GridLevel<Tile>,
+        rng: &mut dyn RngCore,
+    ) -> Result<(), Error> {
+        GridGenerator::generate(&mut self.mazegen, level, rng)?;
+        GridGenerator::generate(&mut self.roomgen, level, rng)?;
+        self.finish(level, rng);
+        Ok(())
+    }
+}
+
+fn corridor_tiles(level: &GridLevel<Tile>, rooms: &[Room]) -> Vec<(usize, usize)> {
+    let mut corridors = Vec::new();
+    for y in 0..level.get_height() {
+        for x in 0..level.get_width() {
+            if let Ok(&Tile::Floor(_)) = level.get_tile(x, y) {
+                if !rooms.iter().any(|room| room.contains(x, y)) {
+                    corridors.push((x, y));
+                }
+            }
+        }
+    }
+    corridors
+}
+
+/// Fills corridor dead ends pass by pass until `limit` tiles have been filled. The last pass
+/// fills a random subset of its dead ends. Rooms and doors are left alone.
+fn fill_dead_ends_up_to<R: Rng + ?Sized>(
+    level: &mut GridLevel<Tile>,
+    rooms: &[Room],
+    limit: usize,
+    rng: &mut R,
+) {
+    let mut filled = 0;
+    while filled < limit {
+        let mut deadends: Vec<_> = corridor_tiles(level, rooms)
+            .into_iter()
+            .filter(|&(x, y)| is_deadend(level, x, y))
+            .collect();
+        if deadends.is_empty() {
+            break;
+        }
+        deadends.shuffle(rng);
+        for &(x, y) in deadends.iter().take(limit - filled) {
+            level.tiles[(x, y)] = Tile::Wall(0);
+            filled += 1;
+        }
     }
 }
@@ -1,12 +1,15 @@
 use std::{mem, ops::Deref};
 
-use rand::{rngs::OsRng, Rng};
+use rand::{rngs::OsRng, Rng, RngCore, SeedableRng};
+use rand_xorshift::XorShiftRng;
 
 use rayon::prelude::*;
 
 use tile::Faction;
 
+use generator::GridGenerator;
 use level::GridLevel;
+use util::Error;
 
 // Constants for 128 bit lehmer rngs
 // const LEHMER_MULT0: u128 = 0x9cec0193f9cb55c4acce1fe16e62b05f;
@@ -78,28 +81,69 @@ fn init_lehmer(seed: Seed) -> Seed {
 
 pub struct FactionGen {
     seed: Seed,
+    buffer: Option<GridLevel<Faction>>,
 }
 
 impl FactionGen {
     pub fn new() -> Self {
+        Self::from_rng(&mut OsRng::new().unwrap())
+    }
+
+    pub fn with_seed(seed: u64) -> Self {
+        Self::from_rng(&mut XorShiftRng::seed_from_u64(seed))
+    }
+
+    /// Creates a `FactionGen` that takes its initial state from `rng`.
+    pub fn from_rng<R: Rng>(rng: &mut R) -> Self {
         Self {
-            seed: init_lehmer(OsRng::new().unwrap().gen::<Seed>()),
+            seed: init_lehmer(rng.gen::<Seed>()),
+            buffer: None,
         }
     }
-    pub fn generate(&mut self, level: &mut GridLevel<Faction>, buffer: &mut GridLevel<Faction>) {
-        assert_eq!(level.get_width(), buffer.get_width());
-        assert_eq!(level.get_height(), buffer.get_height());
-        self.seed = tick(
-            self.seed,
-            level.get_width(),
-            level.get_height(),
-            &level.tiles.data[..],
-            &mut buffer.tiles.data[..],
-        );
-        mem::swap(level, buffer);
+
+    /// Runs a single tick from `level` into `buffer` and swaps them.
+    /// Both levels have to be equally large.
+    pub fn generate(
+        &mut self,
+        level: &mut GridLevel<Faction>,
+        buffer: &mut GridLevel<Faction>,
+    ) -> Result<(), Error> {
+        level.check_same_size(buffer)?;
+        self.seed = step(self.seed, level, buffer);
+        Ok(())
+    }
+}
+
+/// Runs a single tick. The generator keeps its own buffer level between calls so that
+/// it can be driven like any other grid generator.
+impl GridGenerator<Faction> for FactionGen {
+    fn generate(
+        &mut self,
+        level: &mut GridLevel<Faction>,
+        rng: &mut dyn RngCore,
+    ) -> Result<(), Error> {
+        let mut buffer = match self.buffer.take() {
+            Some(buffer) if level.check_same_size(&buffer).is_ok() => buffer,
+            _ => level.clone(),
+        };
+        step(init_lehmer(rng.gen::<Seed>()), level, &mut buffer);
+        self.buffer = Some(buffer);
+        Ok(())
     }
 }
 
+fn step(seed: Seed, level: &mut GridLevel<Faction>, buffer: &mut GridLevel<Faction>) -> Seed {
+    let seed = tick(
+        seed,
+        level.get_width(),
+        level.get_height(),
+        &level.tiles.data[..],
+        &mut buffer.tiles.data[..],
+    );
+    mem::swap(level, buffer);
+    seed
+}
+
 fn select(deck: &[usize], n: usize) -> usize {
     deck[n % deck.len()]
 }
@@ -1,39 +1,153 @@
-use level::{add_isize_to_usize, GridLevel};
+use generator::GridGenerator;
+use level::{add_isize_to_usize, braid_dead_ends, GridLevel};
 
-use rand::{rngs::OsRng, Rng, SeedableRng, XorShiftRng};
+use rand::{rngs::OsRng, seq::SliceRandom, Rng, RngCore, SeedableRng};
+use rand_xorshift::XorShiftRng;
 use Vector;
 
 use tile::Tile;
-use util::Direction;
+use util::{random_index, Direction, Error, UnionFind};
 
+/// Carves a maze into the walls of a level, starting at `pos`.
+///
+/// All algorithms except `MazeAlgorithm::Backtracker` work on a lattice of cells that are two
+/// tiles apart and include `pos`. They only use cells that are walls and only connect cells
+/// through walls, so areas that were carved before are left alone.
 pub struct MazeGen {
     pub pos: Vector<usize>,
+    algorithm: MazeAlgorithm,
+    braid: f64,
     rand: XorShiftRng,
 }
 
+/// The algorithm that `MazeGen` uses. Each one gives mazes a different texture.
+#[derive(Clone, Debug, PartialEq)]
+pub enum MazeAlgorithm {
+    /// Randomized depth-first search from tile to tile, which gives long and winding corridors.
+    /// Unlike the other algorithms it doesn't use a lattice and fills every bit of wall it can
+    /// reach. This is the default.
+    Backtracker,
+    /// Randomized Prim's algorithm, which gives lots of short dead ends.
+    Prim,
+    /// Randomized Kruskal's algorithm. Connects every cell of the lattice, not just the ones
+    /// that can be reached from `pos`.
+    Kruskal,
+    /// Wilson's algorithm, which picks any possible maze with the same chance.
+    Wilson,
+    /// Eller's algorithm, which builds the maze row by row. Carves cells of the lattice that
+    /// can't be reached from `pos` as well, but pre-carved areas that block the links between
+    /// rows can leave parts of the maze unconnected.
+    Eller,
+    /// Connects every cell to the cell above or to the left of it, which gives a strong
+    /// diagonal bias and two straight corridors along the top and left.
+    BinaryTree,
+    /// The growing tree algorithm. The value is the chance to continue from a random cell
+    /// instead of the newest one, so 0 gives a depth-first search and 1 behaves like Prim's.
+    GrowingTree(f64),
+}
+
 impl MazeGen {
     pub fn new(x: usize, y: usize) -> MazeGen {
+        MazeGen::from_rng(x, y, &mut OsRng::new().unwrap())
+    }
+
+    /// Creates a `MazeGen` after checking that it would carve something into `level`.
+    /// The start position has to be a wall inside the level. Like `new` it is seeded from the
+    /// operating system; call `reseed` on it to get the same output as `with_seed`.
+    pub fn try_new(x: usize, y: usize, level: &GridLevel<Tile>) -> Result<MazeGen, Error> {
+        match level.get_tile(x, y) {
+            Ok(&Tile::Wall(_)) => Ok(MazeGen::new(x, y)),
+            Ok(tile) => Err(Error::InvalidConfig(format!(
+                "MazeGen: start position ({}, {}) is {:?} instead of a wall, nothing would be carved",
+                x, y, tile
+            ))),
+            Err(_) => Err(Error::InvalidConfig(format!(
+                "MazeGen: start position ({}, {}) is outside the {}x{} level",
+                x,
+                y,
+                level.get_width(),
+                level.get_height()
+            ))),
+        }
+    }
+
+    pub fn with_seed(x: usize, y: usize, seed: u64) -> MazeGen {
         MazeGen {
             pos: Vector::new(x, y),
-            rand: XorShiftRng::from_rng(&mut OsRng::new().unwrap()).unwrap(),
+            algorithm: MazeAlgorithm::Backtracker,
+            braid: 0.,
+            rand: XorShiftRng::seed_from_u64(seed),
         }
     }
 
+    pub fn from_rng<R: Rng>(x: usize, y: usize, rng: &mut R) -> MazeGen {
+        MazeGen {
+            pos: Vector::new(x, y),
+            algorithm: MazeAlgorithm::Backtracker,
+            braid: 0.,
+            rand: XorShiftRng::from_rng(rng).unwrap(),
+        }
+    }
+
+    pub fn with_algorithm(mut self, algorithm: MazeAlgorithm) -> MazeGen {
+        self.algorithm = algorithm;
+        self
+    }
+
+    /// Sets the fraction of dead ends that are turned into loops with `braid_dead_ends`
+    /// after the maze has been carved. Defaults to 0, which gives perfect mazes.
+    pub fn with_braid(mut self, braid: f64) -> MazeGen {
+        self.braid = braid;
+        self
+    }
+
+    pub fn reseed(&mut self, seed: u64) {
+        self.rand = XorShiftRng::seed_from_u64(seed);
+    }
+
     pub fn generate(&mut self, level: &mut GridLevel<Tile>) {
-        use util::Error;
+        let mut rand = self.rand.clone();
+        self.generate_with(level, &mut rand);
+        self.rand = rand;
+    }
+
+    fn generate_with<R: Rng + ?Sized>(&self, level: &mut GridLevel<Tile>, rng: &mut R) {
+        self.carve_with(level, rng);
+        if self.braid > 0. {
+            braid_dead_ends(level, self.braid, rng);
+        }
+    }
+
+    fn carve_with<R: Rng + ?Sized>(&self, level: &mut GridLevel<Tile>, rng: &mut R) {
+        let lattice = Lattice::new(level, (self.pos.x, self.pos.y));
+        match (&self.algorithm, lattice.start) {
+            (&MazeAlgorithm::Backtracker, _) => Self::carve(self.pos, level, rng),
+            (_, None) => {}
+            (&MazeAlgorithm::Prim, Some(start)) => prim(&lattice, start, level, rng),
+            (&MazeAlgorithm::Kruskal, Some(_)) => kruskal(&lattice, level, rng),
+            (&MazeAlgorithm::Wilson, Some(start)) => wilson(&lattice, start, level, rng),
+            (&MazeAlgorithm::Eller, Some(_)) => eller(&lattice, level, rng),
+            (&MazeAlgorithm::BinaryTree, Some(_)) => binary_tree(&lattice, level, rng),
+            (&MazeAlgorithm::GrowingTree(chance), Some(start)) => {
+                growing_tree(&lattice, start, chance, level, rng)
+            }
+        }
+    }
+
+    fn carve<R: Rng + ?Sized>(pos: Vector<usize>, level: &mut GridLevel<Tile>, rng: &mut R) {
         let mut stack: Vec<Vector<usize>> = Vec::new();
-        stack.push(self.pos);
+        stack.push(pos);
         'mainloop: while let Some(cur) = stack.pop() {
             let neighbours = Self::get_neighbours(level, &cur);
             match level.get_mut_tile_with_vec(&cur) {
                 Ok(tile) => {
                     match *tile {
-                        Tile::Void(_) | Tile::Floor(_) => continue 'mainloop,
-                        _ => {}
+                        Tile::Wall(_) => {}
+                        _ => continue 'mainloop,
                     }
                     match neighbours {
                         Some(mut neighbours) => {
-                            self.rand.shuffle(&mut neighbours);
+                            neighbours.shuffle(rng);
                             while let Some(p) = neighbours.pop() {
                                 stack.push(p);
                             }
@@ -43,7 +157,7 @@ impl MazeGen {
                     }
                 }
 
-                Err(Error::IndexOutOfBounds) => continue 'mainloop,
+                Err(_) => continue 'mainloop,
             }
         }
     }
@@ -62,7 +176,7 @@ impl MazeGen {
                 _ => continue,
             };
             match level.get_tile_with_tuple(coord) {
-                Ok(&Tile::Floor(_)) => {
+                Ok(tile) if tile.is_passable() => {
                     floors += 1;
                     if floors > 1 {
                         return None;
@@ -75,3 +189,300 @@ impl MazeGen {
         Some(neighbours)
     }
 }
+
+impl GridGenerator<Tile> for MazeGen {
+    fn generate(
+        &mut self,
+        level: &mut GridLevel<Tile>,
+        rng: &mut dyn RngCore,
+    ) -> Result<(), Error> {
+        self.generate_with(level, rng);
+        Ok(())
+    }
+}
+
+/// The cells used by the lattice based algorithms. Cells are numbered row by row.
+struct Lattice {
+    origin: (usize, usize),
+    cols: usize,
+    rows: usize,
+    start: Option<usize>,
+    /// Whether a cell is a wall and can become part of the maze.
+    usable: Vec<bool>,
+    /// Whether a cell can be connected to the cell right of it.
+    right: Vec<bool>,
+    /// Whether a cell can be connected to the cell below it.
+    down: Vec<bool>,
+}
+
+impl Lattice {
+    fn new(level: &GridLevel<Tile>, pos: (usize, usize)) -> Lattice {
+        let origin = (pos.0 % 2, pos.1 % 2);
+        let cols = (level.get_width() + 1).saturating_sub(origin.0) / 2;
+        let rows = (level.get_height() + 1).saturating_sub(origin.1) / 2;
+        let wall = |(x, y): (usize, usize)| matches!(level.get_tile(x, y), Ok(&Tile::Wall(_)));
+        let mut lattice = Lattice {
+            origin,
+            cols,
+            rows,
+            start: None,
+            usable: Vec::with_capacity(cols * rows),
+            right: Vec::with_capacity(cols * rows),
+            down: Vec::with_capacity(cols * rows),
+        };
+        for cell in 0..cols * rows {
+            let (x, y) = lattice.tile(cell);
+            lattice.usable.push(wall((x, y)));
+        }
+        for cell in 0..cols * rows {
+            let (x, y) = lattice.tile(cell);
+            let usable = lattice.usable[cell];
+            let col = cell % cols;
+            let row = cell / cols;
+            lattice
+                .right
+                .push(usable && col + 1 < cols && lattice.usable[cell + 1] && wall((x + 1, y)));
+            lattice
+                .down
+                .push(usable && row + 1 < rows && lattice.usable[cell + cols] && wall((x, y + 1)));
+        }
+        if pos.0 < level.get_width() && pos.1 < level.get_height() {
+            let start = pos.0 / 2 + pos.1 / 2 * cols;
+            if lattice.usable[start] {
+                lattice.start = Some(start);
+            }
+        }
+        lattice
+    }
+
+    fn len(&self) -> usize {
+        self.usable.len()
+    }
+
+    fn tile(&self, cell: usize) -> (usize, usize) {
+        (
+            self.origin.0 + 2 * (cell % self.cols),
+            self.origin.1 + 2 * (cell / self.cols),
+        )
+    }
+
+    fn neighbours(&self, cell: usize) -> Vec<usize> {
+        let mut neighbours = Vec::with_capacity(4);
+        let col = cell % self.cols;
+        if self.right[cell] {
+            neighbours.push(cell + 1);
+        }
+        if self.down[cell] {
+            neighbours.push(cell + self.cols);
+        }
+        if col > 0 && self.right[cell - 1] {
+            neighbours.push(cell - 1);
+        }
+        if cell >= self.cols && self.down[cell - self.cols] {
+            neighbours.push(cell - self.cols);
+        }
+        neighbours
+    }
+
+    fn open(&self, level: &mut GridLevel<Tile>, cell: usize) {
+        level.tiles[self.tile(cell)] = Tile::Floor(0);
+    }
+
+    /// Opens both cells and the wall between them.
+    fn connect(&self, level: &mut GridLevel<Tile>, a: usize, b: usize) {
+        let (ax, ay) = self.tile(a);
+        let (bx, by) = self.tile(b);
+        self.open(level, a);
+        self.open(level, b);
+        level.tiles[((ax + bx) / 2, (ay + by) / 2)] = Tile::Floor(0);
+    }
+}
+
+fn prim<R: Rng + ?Sized>(
+    lattice: &Lattice,
+    start: usize,
+    level: &mut GridLevel<Tile>,
+    rng: &mut R,
+) {
+    let mut in_maze = vec![false; lattice.len()];
+    let mut in_frontier = vec![false; lattice.len()];
+    let mut frontier = vec![start];
+    in_frontier[start] = true;
+    lattice.open(level, start);
+    while !frontier.is_empty() {
+        let cell = frontier.swap_remove(random_index(rng, frontier.len()));
+        let neighbours = lattice.neighbours(cell);
+        let linked: Vec<usize> = neighbours.iter().cloned().filter(|&n| in_maze[n]).collect();
+        if let Some(&other) = linked.choose(rng) {
+            lattice.connect(level, cell, other);
+        }
+        in_maze[cell] = true;
+        for n in neighbours {
+            if !in_maze[n] && !in_frontier[n] {
+                in_frontier[n] = true;
+                frontier.push(n);
+            }
+        }
+    }
+}
+
+fn kruskal<R: Rng + ?Sized>(lattice: &Lattice, level: &mut GridLevel<Tile>, rng: &mut R) {
+    let mut edges = Vec::new();
+    for cell in 0..lattice.len() {
+        if lattice.usable[cell] {
+            lattice.open(level, cell);
+        }
+        if lattice.right[cell] {
+            edges.push((cell, cell + 1));
+        }
+        if lattice.down[cell] {
+            edges.push((cell, cell + lattice.cols));
+        }
+    }
+    edges.shuffle(rng);
+    let mut sets = UnionFind::new(lattice.len());
+    for (a, b) in edges {
+        if sets.union(a, b) {
+            lattice.connect(level, a, b);
+        }
+    }
+}
+
+fn wilson<R: Rng + ?Sized>(
+    lattice: &Lattice,
+    start: usize,
+    level: &mut GridLevel<Tile>,
+    rng: &mut R,
+) {
+    // Walks only terminate if they stay in the part of the lattice that contains `start`.
+    let mut reachable = vec![false; lattice.len()];
+    let mut cells = vec![start];
+    reachable[start] = true;
+    let mut i = 0;
+    while i < cells.len() {
+        for n in lattice.neighbours(cells[i]) {
+            if !reachable[n] {
+                reachable[n] = true;
+                cells.push(n);
+            }
+        }
+        i += 1;
+    }
+    cells.shuffle(rng);
+
+    let mut in_maze = vec![false; lattice.len()];
+    let mut next = vec![0; lattice.len()];
+    in_maze[start] = true;
+    lattice.open(level, start);
+    for &cell in &cells {
+        // Loops are erased by overwriting the direction a cell was left in.
+        let mut current = cell;
+        while !in_maze[current] {
+            next[current] = *lattice.neighbours(current).choose(rng).unwrap();
+            current = next[current];
+        }
+        let mut current = cell;
+        while !in_maze[current] {
+            lattice.connect(level, current, next[current]);
+            in_maze[current] = true;
+            current = next[current];
+        }
+    }
+}
+
+fn eller<R: Rng + ?Sized>(lattice: &Lattice, level: &mut GridLevel<Tile>, rng: &mut R) {
+    let cols = lattice.cols;
+    let mut sets = UnionFind::new(lattice.len());
+    for row in 0..lattice.rows {
+        let last = row + 1 == lattice.rows;
+        let cells = row * cols..(row + 1) * cols;
+        for cell in cells.clone() {
+            if lattice.usable[cell] {
+                lattice.open(level, cell);
+            }
+            // The last row joins everything that isn't connected yet.
+            if lattice.right[cell] && (last || rng.gen()) && sets.union(cell, cell + 1) {
+                lattice.connect(level, cell, cell + 1);
+            }
+        }
+        if last {
+            break;
+        }
+
+        // Every set continues downwards at least once if it can.
+        let mut groups: Vec<(usize, Vec<usize>)> = Vec::new();
+        for cell in cells.filter(|&cell| lattice.down[cell]) {
+            let root = sets.find(cell);
+            match groups.iter_mut().find(|group| group.0 == root) {
+                Some(group) => group.1.push(cell),
+                None => groups.push((root, vec![cell])),
+            }
+        }
+        for (_, mut group) in groups {
+            group.shuffle(rng);
+            for (i, &cell) in group.iter().enumerate() {
+                if i == 0 || rng.gen() {
+                    sets.union(cell, cell + cols);
+                    lattice.connect(level, cell, cell + cols);
+                }
+            }
+        }
+    }
+}
+
+fn binary_tree<R: Rng + ?Sized>(lattice: &Lattice, level: &mut GridLevel<Tile>, rng: &mut R) {
+    let cols = lattice.cols;
+    for cell in 0..lattice.len() {
+        if !lattice.usable[cell] {
+            continue;
+        }
+        lattice.open(level, cell);
+        let col = cell % cols;
+        let mut options = Vec::with_capacity(2);
+        if col > 0 && lattice.right[cell - 1] {
+            options.push(cell - 1);
+        }
+        if cell >= cols && lattice.down[cell - cols] {
+            options.push(cell - cols);
+        }
+        if let Some(&other) = options.choose(rng) {
+            lattice.connect(level, cell, other);
+        }
+    }
+}
+
+fn growing_tree<R: Rng + ?Sized>(
+    lattice: &Lattice,
+    start: usize,
+    chance: f64,
+    level: &mut GridLevel<Tile>,
+    rng: &mut R,
+) {
+    let mut visited = vec![false; lattice.len()];
+    let mut active = vec![start];
+    visited[start] = true;
+    lattice.open(level, start);
+    while !active.is_empty() {
+        let i = if rng.gen::<f64>() < chance {
+            random_index(rng, active.len())
+        } else {
+            active.len() - 1
+        };
+        let cell = active[i];
+        let options: Vec<usize> = lattice
+            .neighbours(cell)
+            .into_iter()
+            .filter(|&n| !visited[n])
+            .collect();
+        match options.choose(rng) {
+            Some(&next) => {
+                lattice.connect(level, cell, next);
+                visited[next] = true;
+                active.push(next);
+            }
+            None => {
+                active.remove(i);
+            }
+        }
+    }
+}
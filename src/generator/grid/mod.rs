@@ -1,4 +1,13 @@
+pub mod autotile;
+pub mod bsp;
+pub mod cave;
+pub mod connector;
 pub mod dungeon;
 pub mod faction;
 pub mod maze;
+pub mod pipeline;
+pub mod prefab;
 pub mod room;
+pub mod stairs;
+pub mod walker;
+pub mod wfc;
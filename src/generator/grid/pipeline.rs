@@ -0,0 +1,253 @@
+use rand::RngCore;
+
+use generator::GridGenerator;
+use level::GridLevel;
+
+/// An ordered list of generation steps that can be run on a `GridLevel`.
+///
+/// Steps are generators, post-processors and validators. The builder methods
+/// `repeat_until_stable` and `only_if` modify the step that was added last.
+///
+/// ```
+/// # extern crate dungenon;
+/// # extern crate rand;
+/// # use dungenon::generator::{MazeGen, Pipeline, RoomGen};
+/// # use dungenon::level::{fill_dead_end_tiles, GridLevel};
+/// # use dungenon::tile::Tile;
+/// # fn main() {
+/// let mut pipeline = Pipeline::new()
+///     .generator("maze", MazeGen::new(1, 1))
+///     .generator("rooms", RoomGen::new(3, 8, 2, 100))
+///     .process("fill dead ends", fill_dead_end_tiles)
+///     .repeat_until_stable(1000)
+///     .validate("not empty", |level: &GridLevel<Tile>| {
+///         let floors = (0..level.get_height())
+///             .flat_map(|y| (0..level.get_width()).map(move |x| (x, y)))
+///             .filter(|&(x, y)| matches!(level.get_tile(x, y), Ok(&Tile::Floor(_))))
+///             .count();
+///         if floors > 0 {
+///             Ok(())
+///         } else {
+///             Err("everything was filled in".to_owned())
+///         }
+///     });
+/// let mut level = GridLevel::new_filled_with(Tile::Wall(0), 40, 40);
+/// let report = pipeline.run(&mut level, &mut rand::thread_rng());
+/// assert!(report.succeeded());
+/// # }
+/// ```
+pub struct Pipeline<T> {
+    steps: Vec<Step<T>>,
+}
+
+type Condition<T> = Box<dyn Fn(&GridLevel<T>) -> bool>;
+type Process<T> = Box<dyn FnMut(&mut GridLevel<T>) -> bool>;
+type Validator<T> = Box<dyn Fn(&GridLevel<T>) -> Result<(), String>>;
+
+struct Step<T> {
+    name: String,
+    action: Action<T>,
+    condition: Option<Condition<T>>,
+    max_runs: usize,
+}
+
+enum Action<T> {
+    Generate(Box<dyn GridGenerator<T>>),
+    Process(Process<T>),
+    Validate(Validator<T>),
+}
+
+/// What happened to every step that the pipeline reached.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PipelineReport {
+    pub steps: Vec<StepReport>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct StepReport {
+    pub name: String,
+    pub outcome: StepOutcome,
+    /// How many times the step was run. Zero for skipped steps.
+    pub runs: usize,
+    /// Number of tiles that differ between the level before and after the step.
+    pub changed_tiles: usize,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum StepOutcome {
+    Completed,
+    /// The condition given with `only_if` did not hold.
+    Skipped,
+    /// A validator rejected the level or a generator returned an error.
+    /// The pipeline stops after a failed step.
+    Failed(String),
+}
+
+impl<T: Clone + PartialEq> Pipeline<T> {
+    pub fn new() -> Pipeline<T> {
+        Pipeline { steps: Vec::new() }
+    }
+
+    /// Adds a step that runs `generator` with the pipeline's rng.
+    pub fn generator<G>(self, name: &str, generator: G) -> Pipeline<T>
+    where
+        G: GridGenerator<T> + 'static,
+    {
+        self.push(name, Action::Generate(Box::new(generator)))
+    }
+
+    /// Adds a post-processing step. `process` returns whether it changed anything,
+    /// just like `fill_dead_end_tiles` does.
+    pub fn process<F>(self, name: &str, process: F) -> Pipeline<T>
+    where
+        F: FnMut(&mut GridLevel<T>) -> bool + 'static,
+    {
+        self.push(name, Action::Process(Box::new(process)))
+    }
+
+    /// Adds a step that checks the level and stops the pipeline with the returned message
+    /// if the check fails.
+    pub fn validate<F>(self, name: &str, validator: F) -> Pipeline<T>
+    where
+        F: Fn(&GridLevel<T>) -> Result<(), String> + 'static,
+    {
+        self.push(name, Action::Validate(Box::new(validator)))
+    }
+
+    /// Reruns the last added step until a run leaves the level unchanged,
+    /// but at most `max_runs` times in total.
+    ///
+    /// # Panics
+    /// Panics if no step has been added yet.
+    pub fn repeat_until_stable(mut self, max_runs: usize) -> Pipeline<T> {
+        self.last_step("repeat_until_stable").max_runs = max_runs;
+        self
+    }
+
+    /// Runs the last added step only if `condition` holds for the level at that point.
+    ///
+    /// # Panics
+    /// Panics if no step has been added yet.
+    pub fn only_if<F>(mut self, condition: F) -> Pipeline<T>
+    where
+        F: Fn(&GridLevel<T>) -> bool + 'static,
+    {
+        self.last_step("only_if").condition = Some(Box::new(condition));
+        self
+    }
+
+    pub fn run(&mut self, level: &mut GridLevel<T>, rng: &mut dyn RngCore) -> PipelineReport {
+        let mut report = PipelineReport { steps: Vec::new() };
+        for step in &mut self.steps {
+            let step_report = step.run(level, rng);
+            let failed = step_report.failed();
+            report.steps.push(step_report);
+            if failed {
+                break;
+            }
+        }
+        report
+    }
+
+    fn push(mut self, name: &str, action: Action<T>) -> Pipeline<T> {
+        self.steps.push(Step {
+            name: name.to_owned(),
+            action,
+            condition: None,
+            max_runs: 1,
+        });
+        self
+    }
+
+    fn last_step(&mut self, method: &str) -> &mut Step<T> {
+        match self.steps.last_mut() {
+            Some(step) => step,
+            None => panic!("Pipeline::{} must be called after adding a step", method),
+        }
+    }
+}
+
+impl<T: Clone + PartialEq> Default for Pipeline<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Clone + PartialEq> Step<T> {
+    fn run(&mut self, level: &mut GridLevel<T>, rng: &mut dyn RngCore) -> StepReport {
+        let mut report = StepReport {
+            name: self.name.clone(),
+            outcome: StepOutcome::Completed,
+            runs: 0,
+            changed_tiles: 0,
+        };
+        if let Some(ref condition) = self.condition {
+            if !condition(level) {
+                report.outcome = StepOutcome::Skipped;
+                return report;
+            }
+        }
+        if let Action::Validate(ref validator) = self.action {
+            report.runs = 1;
+            if let Err(message) = validator(level) {
+                report.outcome = StepOutcome::Failed(message);
+            }
+            return report;
+        }
+
+        let original = level.clone();
+        while report.runs < self.max_runs {
+            let before = level.clone();
+            let changed = match self.action {
+                Action::Generate(ref mut generator) => {
+                    if let Err(error) = generator.generate(level, rng) {
+                        report.runs += 1;
+                        report.outcome = StepOutcome::Failed(error.to_string());
+                        break;
+                    }
+                    true
+                }
+                Action::Process(ref mut process) => process(level),
+                Action::Validate(_) => unreachable!(),
+            };
+            report.runs += 1;
+            if !changed || count_changes(&before, level) == 0 {
+                break;
+            }
+        }
+        report.changed_tiles = count_changes(&original, level);
+        report
+    }
+}
+
+impl PipelineReport {
+    /// Returns true if no validator failed.
+    pub fn succeeded(&self) -> bool {
+        self.steps.iter().all(|step| !step.failed())
+    }
+
+    /// Total number of tiles changed over the whole run. A tile changed by several steps
+    /// is counted once per step.
+    pub fn changed_tiles(&self) -> usize {
+        self.steps.iter().map(|step| step.changed_tiles).sum()
+    }
+}
+
+impl StepReport {
+    pub fn failed(&self) -> bool {
+        matches!(self.outcome, StepOutcome::Failed(_))
+    }
+}
+
+fn count_changes<T: PartialEq>(before: &GridLevel<T>, after: &GridLevel<T>) -> usize {
+    if before.get_width() != after.get_width() || before.get_height() != after.get_height() {
+        return after.tiles.data.len();
+    }
+    before
+        .tiles
+        .data
+        .iter()
+        .zip(after.tiles.data.iter())
+        .filter(|&(a, b)| a != b)
+        .count()
+}
@@ -0,0 +1,375 @@
+use Point;
+
+use rand::{rngs::OsRng, Rng, RngCore, SeedableRng};
+use rand_xorshift::XorShiftRng;
+
+use generator::grid::room::Room;
+use generator::{GridGenerator, RoomGen};
+use level::{CharMap, GridLevel};
+use tile::Tile;
+use util::{random_index, Direction, Error, Grid};
+
+/// A hand-authored chunk of a level, like a vault, a shrine or a boss arena.
+///
+/// Cells without a tile are "don't care" cells: stamping the prefab leaves the level's tile
+/// there untouched. The first row of a template is the row with the smallest y coordinate.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Prefab {
+    cells: Grid<Option<Tile>>,
+}
+
+impl Prefab {
+    /// Parses a template with one row per line, using the characters of `CharMap::default`
+    /// except for `_` instead of a space for void, and `?` for don't care cells. Empty lines
+    /// before and after the template are ignored, so templates can be written as indented raw
+    /// strings as long as every row is indented the same.
+    pub fn from_ascii(template: &str) -> Result<Prefab, Error> {
+        Prefab::from_ascii_with(
+            template,
+            &CharMap::default().with_kind(Tile::Void, '_'),
+            '?',
+        )
+    }
+
+    /// Parses a template with the characters of `map`. `keep` marks don't care cells and
+    /// takes precedence over `map`.
+    pub fn from_ascii_with(template: &str, map: &CharMap, keep: char) -> Result<Prefab, Error> {
+        let lines: Vec<&str> = template.lines().collect();
+        let first = lines.iter().position(|line| !line.trim().is_empty());
+        let last = lines.iter().rposition(|line| !line.trim().is_empty());
+        let rows = match (first, last) {
+            (Some(first), Some(last)) => &lines[first..=last],
+            _ => return Err(Error::Format("the prefab template is empty".to_owned())),
+        };
+        // Counted in characters, since whitespace isn't always one byte long.
+        let indent = rows
+            .iter()
+            .map(|row| row.chars().take_while(|c| c.is_whitespace()).count())
+            .min()
+            .unwrap_or(0);
+        let rows: Vec<String> = rows
+            .iter()
+            .map(|row| row.chars().skip(indent).collect())
+            .collect();
+
+        let width = rows[0].trim_end().chars().count();
+        let mut cells = Grid::new_filled_with(None, width, rows.len());
+        for (y, row) in rows.iter().enumerate() {
+            let row = row.trim_end();
+            if row.chars().count() != width {
+                return Err(Error::Format(format!(
+                    "row {} of the prefab template is {} cells wide instead of {}",
+                    y,
+                    row.chars().count(),
+                    width
+                )));
+            }
+            for (x, c) in row.chars().enumerate() {
+                if c == keep {
+                    continue;
+                }
+                cells[(x, y)] = map.tile_for(c).map(Some).ok_or_else(|| {
+                    Error::Format(format!(
+                        "unknown character '{}' at ({}, {}) of the prefab template",
+                        c, x, y
+                    ))
+                })?;
+            }
+        }
+        Ok(Prefab { cells })
+    }
+
+    pub fn width(&self) -> usize {
+        self.cells.get_width()
+    }
+
+    pub fn height(&self) -> usize {
+        self.cells.get_height()
+    }
+
+    /// The tile at `(x, y)` or `None` for don't care cells and positions outside the prefab.
+    pub fn get(&self, x: usize, y: usize) -> Option<&Tile> {
+        if x < self.width() && y < self.height() {
+            self.cells[(x, y)].as_ref()
+        } else {
+            None
+        }
+    }
+
+    /// The prefab turned by 90 degrees, clockwise when the first row is drawn on top.
+    pub fn rotated(&self) -> Prefab {
+        let (width, height) = (self.width(), self.height());
+        let mut cells = Grid::new_filled_with(None, height, width);
+        for y in 0..width {
+            for x in 0..height {
+                cells[(x, y)] = self.cells[(y, height - 1 - x)].clone();
+            }
+        }
+        Prefab { cells }
+    }
+
+    /// The prefab flipped horizontally.
+    pub fn mirrored(&self) -> Prefab {
+        let width = self.width();
+        let mut cells = self.cells.clone();
+        for y in 0..self.height() {
+            for x in 0..width {
+                cells[(x, y)] = self.cells[(width - 1 - x, y)].clone();
+            }
+        }
+        Prefab { cells }
+    }
+
+    /// The distinct orientations of the prefab, starting with the prefab itself. Includes
+    /// the four rotations if `rotate` is set and their mirror images if `mirror` is set.
+    pub fn variants(&self, rotate: bool, mirror: bool) -> Vec<Prefab> {
+        let mut variants: Vec<Prefab> = Vec::new();
+        let mut current = self.clone();
+        for _ in 0..if rotate { 4 } else { 1 } {
+            let next = current.rotated();
+            let mirrored = current.mirrored();
+            if !variants.contains(&current) {
+                variants.push(current);
+            }
+            if mirror && !variants.contains(&mirrored) {
+                variants.push(mirrored);
+            }
+            current = next;
+        }
+        variants
+    }
+
+    /// Copies the prefab into `level` with its first cell at `(x, y)`. Don't care cells keep
+    /// the level's tiles. Nothing is changed if the prefab doesn't fit into the level.
+    pub fn stamp(&self, level: &mut GridLevel<Tile>, x: usize, y: usize) -> Result<(), Error> {
+        let max = match (
+            x.checked_add(self.width() - 1),
+            y.checked_add(self.height() - 1),
+        ) {
+            (Some(max_x), Some(max_y)) => (max_x, max_y),
+            _ => {
+                return Err(Error::IndexOutOfBounds {
+                    pos: (x, y),
+                    size: (level.get_width(), level.get_height()),
+                })
+            }
+        };
+        level.get_tile_with_tuple(max)?;
+        for py in 0..self.height() {
+            for px in 0..self.width() {
+                if let Some(ref tile) = self.cells[(px, py)] {
+                    level.tiles[(x + px, y + py)] = tile.clone();
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// The area the prefab covers when its first cell is at `(x, y)`.
+    fn footprint(&self, id: usize, x: usize, y: usize) -> Room {
+        Room {
+            id,
+            min: Point::new(x, y),
+            max: Point::new(
+                x.saturating_add(self.width() - 1),
+                y.saturating_add(self.height() - 1),
+            ),
+        }
+    }
+}
+
+/// Places prefabs at random positions of a level.
+///
+/// Every attempt picks one of the prefabs and one of its orientations at random and stamps it
+/// if it keeps `room_distance` tiles away from the prefabs placed before and from the rooms to
+/// avoid. The footprints of the placed prefabs can be reserved in a `RoomGen`, so that rooms
+/// generated afterwards stay clear of them.
+pub struct PrefabGen {
+    prefabs: Vec<Prefab>,
+    rotate: bool,
+    mirror: bool,
+    max_count: usize,
+    attempts: u64,
+    touch_corridor: bool,
+    avoid: Vec<Room>,
+    room_distance: usize,
+    placed: Vec<Room>,
+    rand: XorShiftRng,
+}
+
+impl PrefabGen {
+    /// Creates a `PrefabGen` that places a single one of `prefabs` in up to 100 attempts,
+    /// without rotating or mirroring them.
+    pub fn new(prefabs: Vec<Prefab>) -> PrefabGen {
+        PrefabGen::from_rng(prefabs, &mut OsRng::new().unwrap())
+    }
+
+    pub fn with_seed(prefabs: Vec<Prefab>, seed: u64) -> PrefabGen {
+        PrefabGen::from_rng(prefabs, &mut XorShiftRng::seed_from_u64(seed))
+    }
+
+    pub fn from_rng<R: Rng>(prefabs: Vec<Prefab>, rng: &mut R) -> PrefabGen {
+        PrefabGen {
+            prefabs,
+            rotate: false,
+            mirror: false,
+            max_count: 1,
+            attempts: 100,
+            touch_corridor: false,
+            avoid: Vec::new(),
+            room_distance: 0,
+            placed: Vec::new(),
+            rand: XorShiftRng::from_rng(rng).unwrap(),
+        }
+    }
+
+    /// Allows placing the prefabs rotated by multiples of 90 degrees and mirrored.
+    pub fn with_orientations(mut self, rotate: bool, mirror: bool) -> PrefabGen {
+        self.rotate = rotate;
+        self.mirror = mirror;
+        self
+    }
+
+    /// Sets how many prefabs may be placed per call to `generate`.
+    pub fn with_max_count(mut self, max_count: usize) -> PrefabGen {
+        self.max_count = max_count;
+        self
+    }
+
+    pub fn with_attempts(mut self, attempts: u64) -> PrefabGen {
+        self.attempts = attempts;
+        self
+    }
+
+    /// If set, a prefab is only placed where one of the passable cells on its edge is
+    /// orthogonally next to a corridor, that is a passable tile outside of the rooms to avoid
+    /// and the prefabs placed before.
+    pub fn touching_corridors(mut self, touch_corridor: bool) -> PrefabGen {
+        self.touch_corridor = touch_corridor;
+        self
+    }
+
+    /// Keeps prefabs from overlapping `rooms`, for example the rooms of a `RoomGen`.
+    pub fn avoiding_rooms(mut self, rooms: &[Room]) -> PrefabGen {
+        self.avoid = rooms.to_vec();
+        self
+    }
+
+    /// Sets the number of tiles kept between a prefab and other prefabs or rooms to avoid.
+    pub fn with_room_distance(mut self, room_distance: usize) -> PrefabGen {
+        self.room_distance = room_distance;
+        self
+    }
+
+    pub fn reseed(&mut self, seed: u64) {
+        self.rand = XorShiftRng::seed_from_u64(seed);
+    }
+
+    /// The areas covered by the prefabs placed by the last call to `generate`. A footprint's
+    /// id is its index here.
+    pub fn placed(&self) -> &[Room] {
+        &self.placed
+    }
+
+    /// Reserves the footprints of the placed prefabs in `rooms`, so that it doesn't place
+    /// rooms on top of them.
+    pub fn reserve(&self, rooms: &mut RoomGen) {
+        for footprint in &self.placed {
+            rooms.reserve(footprint.clone());
+        }
+    }
+
+    pub fn generate(&mut self, level: &mut GridLevel<Tile>) {
+        let mut rand = self.rand.clone();
+        self.generate_with(level, &mut rand);
+        self.rand = rand;
+    }
+
+    fn generate_with<R: Rng + ?Sized>(&mut self, level: &mut GridLevel<Tile>, rng: &mut R) {
+        self.placed = Vec::new();
+        let variants: Vec<Vec<Prefab>> = self
+            .prefabs
+            .iter()
+            .map(|prefab| prefab.variants(self.rotate, self.mirror))
+            .collect();
+        if variants.is_empty() {
+            return;
+        }
+        let width = level.get_width();
+        let height = level.get_height();
+
+        for _ in 0..self.attempts {
+            if self.placed.len() >= self.max_count {
+                break;
+            }
+            let options = &variants[random_index(rng, variants.len())];
+            let prefab = &options[random_index(rng, options.len())];
+            if prefab.width() > width || prefab.height() > height {
+                continue;
+            }
+            let x = random_index(rng, width - prefab.width() + 1);
+            let y = random_index(rng, height - prefab.height() + 1);
+            let footprint = prefab.footprint(self.placed.len(), x, y);
+            let collides = self
+                .placed
+                .iter()
+                .chain(&self.avoid)
+                .any(|room| room.overlaps(&footprint, self.room_distance));
+            if collides || (self.touch_corridor && !self.touches_corridor(level, prefab, x, y)) {
+                continue;
+            }
+            prefab.stamp(level, x, y).unwrap();
+            self.placed.push(footprint);
+        }
+    }
+
+    fn touches_corridor(
+        &self,
+        level: &GridLevel<Tile>,
+        prefab: &Prefab,
+        x: usize,
+        y: usize,
+    ) -> bool {
+        let is_corridor = |(cx, cy): (usize, usize)| {
+            level.get_tile(cx, cy).is_ok_and(Tile::is_passable)
+                && !self
+                    .placed
+                    .iter()
+                    .chain(&self.avoid)
+                    .any(|room| room.contains(cx, cy))
+        };
+        let (width, height) = (prefab.width(), prefab.height());
+        for py in 0..height {
+            for px in 0..width {
+                let on_edge = px == 0 || py == 0 || px == width - 1 || py == height - 1;
+                if !on_edge || !prefab.get(px, py).is_some_and(Tile::is_passable) {
+                    continue;
+                }
+                let touches = Direction::get_orthogonal_dirs().iter().any(|dir| {
+                    match dir.offset((x + px, y + py)) {
+                        Some((nx, ny)) => {
+                            let outside = nx < x || ny < y || nx >= x + width || ny >= y + height;
+                            outside && is_corridor((nx, ny))
+                        }
+                        None => false,
+                    }
+                });
+                if touches {
+                    return true;
+                }
+            }
+        }
+        false
+    }
+}
+
+impl GridGenerator<Tile> for PrefabGen {
+    fn generate(
+        &mut self,
+        level: &mut GridLevel<Tile>,
+        rng: &mut dyn RngCore,
+    ) -> Result<(), Error> {
+        self.generate_with(level, rng);
+        Ok(())
+    }
+}
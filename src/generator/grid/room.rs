@@ -1,14 +1,22 @@
+use std::cmp;
+
 use Point;
 
 use rand::{
     distributions::{Distribution, Uniform},
     rngs::OsRng,
-    SeedableRng, XorShiftRng,
+    Rng, RngCore, SeedableRng,
 };
+use rand_xorshift::XorShiftRng;
 use tile::Tile;
 
+use generator::GridGenerator;
 use level::GridLevel;
+use util::Error;
 
+/// Places non-overlapping rectangular rooms at random. Rooms have sides between
+/// `min_room_size + 1` and `max_room_size` tiles long and are kept at least `room_distance`
+/// tiles apart.
 pub struct RoomGen {
     rand_x: XorShiftRng,
     rand_y: XorShiftRng,
@@ -17,6 +25,7 @@ pub struct RoomGen {
     room_distance: usize,
     attempts: u64,
     rooms: Vec<Room>,
+    reserved: Vec<Room>,
 }
 
 impl RoomGen {
@@ -25,73 +34,189 @@ impl RoomGen {
         max_room_size: usize,
         room_distance: usize,
         attempts: u64,
+    ) -> RoomGen {
+        RoomGen::from_rng(
+            min_room_size,
+            max_room_size,
+            room_distance,
+            attempts,
+            &mut OsRng::new().unwrap(),
+        )
+    }
+
+    /// Creates a `RoomGen` after checking that its parameters make sense for `level`. Like
+    /// `new` it is seeded from the operating system; call `reseed` on it to get the same
+    /// output as `with_seed`.
+    pub fn try_new(
+        min_room_size: usize,
+        max_room_size: usize,
+        room_distance: usize,
+        attempts: u64,
+        level: &GridLevel<Tile>,
+    ) -> Result<RoomGen, Error> {
+        let invalid = |reason: String| Err(Error::InvalidConfig(format!("RoomGen: {}", reason)));
+        if min_room_size >= max_room_size {
+            return invalid(format!(
+                "min_room_size ({}) has to be smaller than max_room_size ({})",
+                min_room_size, max_room_size
+            ));
+        }
+        if max_room_size > level.get_width() || max_room_size > level.get_height() {
+            return invalid(format!(
+                "rooms with sides of up to {} tiles don't fit into the {}x{} level",
+                max_room_size,
+                level.get_width(),
+                level.get_height()
+            ));
+        }
+        if attempts == 0 {
+            return invalid("attempts must be at least 1".to_owned());
+        }
+        Ok(RoomGen::new(
+            min_room_size,
+            max_room_size,
+            room_distance,
+            attempts,
+        ))
+    }
+
+    pub fn with_seed(
+        min_room_size: usize,
+        max_room_size: usize,
+        room_distance: usize,
+        attempts: u64,
+        seed: u64,
+    ) -> RoomGen {
+        RoomGen::from_rng(
+            min_room_size,
+            max_room_size,
+            room_distance,
+            attempts,
+            &mut XorShiftRng::seed_from_u64(seed),
+        )
+    }
+
+    pub fn from_rng<R: Rng>(
+        min_room_size: usize,
+        max_room_size: usize,
+        room_distance: usize,
+        attempts: u64,
+        rng: &mut R,
     ) -> RoomGen {
         RoomGen {
-            rand_x: XorShiftRng::from_rng(&mut OsRng::new().unwrap()).unwrap(),
-            rand_y: XorShiftRng::from_rng(&mut OsRng::new().unwrap()).unwrap(),
+            rand_x: XorShiftRng::from_rng(&mut *rng).unwrap(),
+            rand_y: XorShiftRng::from_rng(&mut *rng).unwrap(),
             max_room_size: max_room_size,
             min_room_size: min_room_size,
             room_distance: room_distance,
             attempts: attempts,
             rooms: Vec::new(),
+            reserved: Vec::new(),
         }
     }
 
+    pub fn reseed(&mut self, seed: u64) {
+        let mut rng = XorShiftRng::seed_from_u64(seed);
+        self.rand_x = XorShiftRng::from_rng(&mut rng).unwrap();
+        self.rand_y = XorShiftRng::from_rng(&mut rng).unwrap();
+    }
+
     pub fn generate(&mut self, level: &mut GridLevel<Tile>) {
+        let mut rand_x = self.rand_x.clone();
+        let mut rand_y = self.rand_y.clone();
+        self.generate_with(level, &mut rand_x, &mut rand_y);
+        self.rand_x = rand_x;
+        self.rand_y = rand_y;
+    }
+
+    fn generate_with(
+        &mut self,
+        level: &mut GridLevel<Tile>,
+        rand_x: &mut XorShiftRng,
+        rand_y: &mut XorShiftRng,
+    ) {
+        if level.get_width() == 0 || level.get_height() == 0 {
+            return;
+        }
         for _ in 0..self.attempts {
-            let room = self.generate_box(level);
+            let room = self.generate_box(level, rand_x, rand_y);
 
             let fits = !self.check_collisions(&room);
             if fits {
-                self.rooms.push(room);
+                self.rooms.push(Room {
+                    id: self.rooms.len(),
+                    ..room
+                });
             }
         }
         self.carve(level);
     }
 
+    /// Forgets the rooms placed so far and the reserved areas.
     pub fn reset_rooms(&mut self) {
         self.rooms = Vec::new();
+        self.reserved = Vec::new();
+    }
+
+    /// Keeps rooms placed afterwards `room_distance` tiles away from `area` without carving
+    /// it, for example to make space for a prefab.
+    pub fn reserve(&mut self, area: Room) {
+        self.reserved.push(area);
     }
 
-    fn generate_box(&mut self, level: &mut GridLevel<Tile>) -> Room {
-        let min_range_x = Uniform::new(0, level.get_width());
-        let min_range_y = Uniform::new(0, level.get_height());
+    /// The areas reserved with `reserve`.
+    pub fn reserved(&self) -> &[Room] {
+        &self.reserved
+    }
+
+    fn generate_box(
+        &self,
+        level: &GridLevel<Tile>,
+        rand_x: &mut XorShiftRng,
+        rand_y: &mut XorShiftRng,
+    ) -> Room {
+        let max_size = cmp::max(self.max_room_size, self.min_room_size + 1);
+        let size_range = Uniform::new(self.min_room_size as u64, max_size as u64);
+        let size_x = size_range.sample(rand_x) as usize;
+        let size_y = size_range.sample(rand_y) as usize;
+
+        // Rooms are kept inside the level unless they are too large to fit.
+        let width = level.get_width();
+        let height = level.get_height();
+        let min_range_x = Uniform::new(0, cmp::max(width.saturating_sub(size_x), 1) as u64);
+        let min_range_y = Uniform::new(0, cmp::max(height.saturating_sub(size_y), 1) as u64);
         let min = Point::new(
-            min_range_x.sample(&mut self.rand_x),
-            min_range_y.sample(&mut self.rand_y),
+            min_range_x.sample(rand_x) as usize,
+            min_range_y.sample(rand_y) as usize,
         );
-
-        let max_range = Uniform::new(self.min_room_size, self.max_room_size);
-        let mut max = Point::new(
-            max_range.sample(&mut self.rand_x),
-            max_range.sample(&mut self.rand_y),
+        let max = Point::new(
+            cmp::min(min.x + size_x, width - 1),
+            cmp::min(min.y + size_y, height - 1),
         );
-        max.x += min.x;
-        max.y += min.y;
-        max.x += self.room_distance;
-        max.y += self.room_distance;
 
-        Room { min: min, max: max }
+        Room { id: 0, min: min, max: max }
     }
 
     fn check_collisions(&self, room: &Room) -> bool {
-        for b in &self.rooms {
-            if room.overlaps(b) {
+        for b in self.rooms.iter().chain(&self.reserved) {
+            if room.overlaps(b, self.room_distance) {
                 return true;
             }
         }
         false
     }
 
+    /// The rooms placed so far, in the order they were placed. A room's id is its index here.
+    pub fn rooms(&self) -> &[Room] {
+        &self.rooms
+    }
+
     fn carve(&self, level: &mut GridLevel<Tile>) {
-        use util::Error;
-        let room_distance = self.room_distance.clone();
         for room in &self.rooms {
-            for y in room.min.y..room.max.y - room_distance + 1 {
-                for x in room.min.x..room.max.x - room_distance + 1 {
-                    match level.get_mut_tile(x, y) {
-                        Ok(tile) => *tile = Tile::Floor(0),
-                        Err(Error::IndexOutOfBounds) => {}
+            for y in room.min.y..=room.max.y {
+                for x in room.min.x..=room.max.x {
+                    if let Ok(tile) = level.get_mut_tile(x, y) {
+                        *tile = Tile::Floor(0);
                     }
                 }
             }
@@ -99,16 +224,71 @@ impl RoomGen {
     }
 }
 
-struct Room {
-    pub min: Point<usize>,
-    pub max: Point<usize>,
+impl GridGenerator<Tile> for RoomGen {
+    fn generate(
+        &mut self,
+        level: &mut GridLevel<Tile>,
+        rng: &mut dyn RngCore,
+    ) -> Result<(), Error> {
+        let mut rand_x = XorShiftRng::from_rng(&mut *rng).unwrap();
+        let mut rand_y = XorShiftRng::from_rng(rng).unwrap();
+        self.generate_with(level, &mut rand_x, &mut rand_y);
+        Ok(())
+    }
+}
+
+/// The floor area of a placed room. Both corners are inclusive.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Room {
+    pub(crate) id: usize,
+    pub(crate) min: Point<usize>,
+    pub(crate) max: Point<usize>,
 }
 
 impl Room {
-    pub fn overlaps(&self, room: &Room) -> bool {
-        self.min.x <= room.max.x
-            && room.min.x <= self.max.x
-            && self.min.y <= room.max.y
-            && room.min.y <= self.max.y
+    pub fn id(&self) -> usize {
+        self.id
+    }
+
+    /// The corner with the smallest coordinates.
+    pub fn min(&self) -> Point<usize> {
+        self.min
+    }
+
+    /// The corner with the largest coordinates.
+    pub fn max(&self) -> Point<usize> {
+        self.max
+    }
+
+    pub fn width(&self) -> usize {
+        self.max.x - self.min.x + 1
+    }
+
+    pub fn height(&self) -> usize {
+        self.max.y - self.min.y + 1
+    }
+
+    pub fn area(&self) -> usize {
+        self.width() * self.height()
+    }
+
+    /// The center tile, rounded towards `min` for rooms with an even side.
+    pub fn center(&self) -> Point<usize> {
+        Point::new(
+            self.min.x + (self.width() - 1) / 2,
+            self.min.y + (self.height() - 1) / 2,
+        )
+    }
+
+    /// Returns true if the rooms overlap or are closer than `distance` tiles to each other.
+    pub fn overlaps(&self, room: &Room, distance: usize) -> bool {
+        self.min.x <= room.max.x + distance
+            && room.min.x <= self.max.x + distance
+            && self.min.y <= room.max.y + distance
+            && room.min.y <= self.max.y + distance
+    }
+
+    pub fn contains(&self, x: usize, y: usize) -> bool {
+        self.min.x <= x && x <= self.max.x && self.min.y <= y && y <= self.max.y
     }
 }
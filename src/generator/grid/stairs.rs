@@ -0,0 +1,226 @@
+use rand::{rngs::OsRng, seq::SliceRandom, Rng, RngCore, SeedableRng};
+use rand_xorshift::XorShiftRng;
+
+use generator::grid::room::Room;
+use generator::GridGenerator;
+use level::{distance_map, farthest_tile, is_deadend, GridLevel};
+use tile::Tile;
+use util::{Error, Neighbourhood};
+
+/// How far apart `StairPlacer` puts the entrance and the exit, measured along the shortest
+/// orthogonal path between them.
+#[derive(Clone, Debug, PartialEq)]
+pub enum StairDistance {
+    /// As far apart as a double sweep finds: the exit is the tile farthest from the entrance,
+    /// which is the tile farthest from a random one. This is exact when the level has no loops
+    /// and close to the longest distance otherwise.
+    Farthest,
+    /// As close to the given number of steps as possible.
+    Target(u32),
+}
+
+/// Picks an entrance and an exit on the floor tiles of a finished level.
+///
+/// Every attempt picks an entrance and the best exit that can be reached from it. The pair
+/// that fits `StairDistance` best over all attempts is kept. Only plain floor tiles are
+/// considered, so stairs never replace doors. By default the two tiles are turned into
+/// `Tile::StairsUp` and `Tile::StairsDown`.
+pub struct StairPlacer {
+    distance: StairDistance,
+    rooms: Vec<Room>,
+    avoid_dead_ends: bool,
+    mark: bool,
+    attempts: usize,
+    stairs: Option<((usize, usize), (usize, usize))>,
+    rand: XorShiftRng,
+}
+
+impl StairPlacer {
+    /// Creates a `StairPlacer` that puts the stairs as far apart as it can find in 10 attempts.
+    pub fn new() -> StairPlacer {
+        StairPlacer::from_rng(&mut OsRng::new().unwrap())
+    }
+
+    pub fn with_seed(seed: u64) -> StairPlacer {
+        StairPlacer::from_rng(&mut XorShiftRng::seed_from_u64(seed))
+    }
+
+    pub fn from_rng<R: Rng>(rng: &mut R) -> StairPlacer {
+        StairPlacer {
+            distance: StairDistance::Farthest,
+            rooms: Vec::new(),
+            avoid_dead_ends: false,
+            mark: true,
+            attempts: 10,
+            stairs: None,
+            rand: XorShiftRng::from_rng(rng).unwrap(),
+        }
+    }
+
+    pub fn with_distance(mut self, distance: StairDistance) -> StairPlacer {
+        self.distance = distance;
+        self
+    }
+
+    /// Keeps the entrance and the exit out of the same room. `rooms` should be the rooms
+    /// of the level, for example `DungeonGen::rooms`. Tiles outside of the rooms are fine.
+    pub fn in_different_rooms(mut self, rooms: &[Room]) -> StairPlacer {
+        self.rooms = rooms.to_vec();
+        self
+    }
+
+    /// If set, neither stair is put on a dead end.
+    pub fn avoiding_dead_ends(mut self, avoid_dead_ends: bool) -> StairPlacer {
+        self.avoid_dead_ends = avoid_dead_ends;
+        self
+    }
+
+    /// If not set, the level is left as it is and the positions are only available through
+    /// `entrance` and `exit`.
+    pub fn marking_tiles(mut self, mark: bool) -> StairPlacer {
+        self.mark = mark;
+        self
+    }
+
+    /// Sets the number of entrances to try.
+    pub fn with_attempts(mut self, attempts: usize) -> StairPlacer {
+        self.attempts = attempts.max(1);
+        self
+    }
+
+    pub fn reseed(&mut self, seed: u64) {
+        self.rand = XorShiftRng::seed_from_u64(seed);
+    }
+
+    /// The entrance picked by the last successful call to `generate`.
+    pub fn entrance(&self) -> Option<(usize, usize)> {
+        self.stairs.map(|(entrance, _)| entrance)
+    }
+
+    /// The exit picked by the last successful call to `generate`.
+    pub fn exit(&self) -> Option<(usize, usize)> {
+        self.stairs.map(|(_, exit)| exit)
+    }
+
+    /// Picks the stairs. Fails if the level has no two suitable tiles that are connected.
+    pub fn generate(&mut self, level: &mut GridLevel<Tile>) -> Result<(), Error> {
+        let mut rand = self.rand.clone();
+        let result = self.generate_with(level, &mut rand);
+        self.rand = rand;
+        result
+    }
+
+    fn generate_with<R: Rng + ?Sized>(
+        &mut self,
+        level: &mut GridLevel<Tile>,
+        rng: &mut R,
+    ) -> Result<(), Error> {
+        self.stairs = None;
+        let mut candidates = Vec::new();
+        for y in 0..level.get_height() {
+            for x in 0..level.get_width() {
+                if matches!(level.get_tile(x, y), Ok(&Tile::Floor(_)))
+                    && !(self.avoid_dead_ends && is_deadend(level, x, y))
+                {
+                    candidates.push((x, y));
+                }
+            }
+        }
+        let room_of = |(x, y): (usize, usize)| self.rooms.iter().position(|r| r.contains(x, y));
+
+        let mut best = None;
+        for _ in 0..self.attempts {
+            let mut entrance = match candidates.choose(rng) {
+                Some(&entrance) => entrance,
+                None => break,
+            };
+            let mut distances = distance_map(
+                level,
+                &[entrance],
+                Neighbourhood::Four,
+                Tile::is_passable,
+                |_| 1,
+            );
+            if self.distance == StairDistance::Farthest {
+                let mut reachable = GridLevel::new(level.get_width(), level.get_height());
+                for &candidate in &candidates {
+                    reachable.tiles[candidate] = distances.tiles[candidate];
+                }
+                if let Some((farthest, _)) = farthest_tile(&reachable) {
+                    entrance = farthest;
+                    distances = distance_map(
+                        level,
+                        &[entrance],
+                        Neighbourhood::Four,
+                        Tile::is_passable,
+                        |_| 1,
+                    );
+                }
+            }
+            let room = room_of(entrance);
+            let mut exits = Vec::new();
+            let mut exit_score = u32::MAX;
+            for &exit in &candidates {
+                let distance = match distances.tiles[exit] {
+                    Some(distance) if distance > 0 => distance,
+                    _ => continue,
+                };
+                if room.is_some() && room_of(exit) == room {
+                    continue;
+                }
+                let score = self.score(distance);
+                if score < exit_score {
+                    exit_score = score;
+                    exits.clear();
+                }
+                if score == exit_score {
+                    exits.push(exit);
+                }
+            }
+            if let Some(&exit) = exits.choose(rng) {
+                if best.map_or(true, |(score, _, _)| exit_score < score) {
+                    best = Some((exit_score, entrance, exit));
+                }
+            }
+        }
+
+        let (entrance, exit) = match best {
+            Some((_, entrance, exit)) => (entrance, exit),
+            None => {
+                return Err(Error::GenerationFailed(
+                    "StairPlacer: the level has no two connected tiles to put stairs on".to_owned(),
+                ))
+            }
+        };
+        if self.mark {
+            level.tiles[entrance] = Tile::StairsUp(0);
+            level.tiles[exit] = Tile::StairsDown(0);
+        }
+        self.stairs = Some((entrance, exit));
+        Ok(())
+    }
+
+    /// Lower is better.
+    fn score(&self, distance: u32) -> u32 {
+        match self.distance {
+            StairDistance::Farthest => u32::MAX - distance,
+            StairDistance::Target(target) => distance.abs_diff(target),
+        }
+    }
+}
+
+impl Default for StairPlacer {
+    fn default() -> StairPlacer {
+        StairPlacer::new()
+    }
+}
+
+impl GridGenerator<Tile> for StairPlacer {
+    fn generate(
+        &mut self,
+        level: &mut GridLevel<Tile>,
+        rng: &mut dyn RngCore,
+    ) -> Result<(), Error> {
+        self.generate_with(level, rng)
+    }
+}
@@ -0,0 +1,201 @@
+use rand::{rngs::OsRng, Rng, RngCore, SeedableRng};
+use rand_xorshift::XorShiftRng;
+
+use generator::GridGenerator;
+use level::GridLevel;
+use tile::Tile;
+use util::{random_index, Direction, Error};
+use Vector;
+
+/// Carves meandering tunnels and open areas with random walkers (a drunkard's walk).
+///
+/// All walkers start at `pos`, or at the closest tile off the border if `pos` is on it. Every
+/// step each walker may turn into a random orthogonal direction, moves one tile and turns it
+/// into floor. Walkers can spawn new walkers at their position or die, but there is always at
+/// least one. Generation stops when the step budget is used up or enough of the level is
+/// floor. Walkers never leave the level and stay off its outermost tiles, so the carved area
+/// is surrounded by the level's original tiles.
+pub struct WalkerGen {
+    pub pos: Vector<usize>,
+    walkers: usize,
+    max_walkers: usize,
+    steps: usize,
+    target_floor: f64,
+    turn_chance: f64,
+    spawn_chance: f64,
+    death_chance: f64,
+    rand: XorShiftRng,
+}
+
+struct Walker {
+    pos: (usize, usize),
+    dir: usize,
+}
+
+impl WalkerGen {
+    /// Creates a `WalkerGen` with a single walker that turns a quarter of the time and carves
+    /// until 40% of the level is floor or 10000 steps have been taken. Walkers spawn with a
+    /// chance of 5% and die with a chance of 2% per step, up to 10 of them at once.
+    pub fn new(x: usize, y: usize) -> WalkerGen {
+        WalkerGen::from_rng(x, y, &mut OsRng::new().unwrap())
+    }
+
+    pub fn with_seed(x: usize, y: usize, seed: u64) -> WalkerGen {
+        WalkerGen::from_rng(x, y, &mut XorShiftRng::seed_from_u64(seed))
+    }
+
+    pub fn from_rng<R: Rng>(x: usize, y: usize, rng: &mut R) -> WalkerGen {
+        WalkerGen {
+            pos: Vector::new(x, y),
+            walkers: 1,
+            max_walkers: 10,
+            steps: 10_000,
+            target_floor: 0.4,
+            turn_chance: 0.25,
+            spawn_chance: 0.05,
+            death_chance: 0.02,
+            rand: XorShiftRng::from_rng(rng).unwrap(),
+        }
+    }
+
+    /// Sets the number of walkers to start with and the number of walkers
+    /// that may exist at once.
+    pub fn with_walkers(mut self, walkers: usize, max_walkers: usize) -> WalkerGen {
+        self.walkers = walkers.max(1);
+        self.max_walkers = max_walkers.max(self.walkers);
+        self
+    }
+
+    /// Sets the total number of steps all walkers together may take.
+    pub fn with_steps(mut self, steps: usize) -> WalkerGen {
+        self.steps = steps;
+        self
+    }
+
+    /// Sets the fraction of the level that has to be floor for the walkers to stop.
+    pub fn with_target_floor(mut self, target_floor: f64) -> WalkerGen {
+        self.target_floor = target_floor;
+        self
+    }
+
+    /// Sets the chance for a walker to pick a new direction before each step.
+    pub fn with_turn_chance(mut self, turn_chance: f64) -> WalkerGen {
+        self.turn_chance = turn_chance;
+        self
+    }
+
+    /// Sets the chances for a walker to spawn another walker and to die after each step.
+    pub fn with_lifecycle(mut self, spawn_chance: f64, death_chance: f64) -> WalkerGen {
+        self.spawn_chance = spawn_chance;
+        self.death_chance = death_chance;
+        self
+    }
+
+    pub fn reseed(&mut self, seed: u64) {
+        self.rand = XorShiftRng::seed_from_u64(seed);
+    }
+
+    pub fn generate(&mut self, level: &mut GridLevel<Tile>) {
+        let mut rand = self.rand.clone();
+        self.generate_with(level, &mut rand);
+        self.rand = rand;
+    }
+
+    fn generate_with<R: Rng + ?Sized>(&self, level: &mut GridLevel<Tile>, rng: &mut R) {
+        if level.get_tile(self.pos.x, self.pos.y).is_err() {
+            return;
+        }
+        let width = level.get_width();
+        let height = level.get_height();
+        let inner = |size: usize, pos: usize| size < 3 || (pos > 0 && pos < size - 1);
+        let clamp = |size: usize, pos: usize| {
+            if size < 3 {
+                pos
+            } else {
+                pos.max(1).min(size - 2)
+            }
+        };
+        let start = (clamp(width, self.pos.x), clamp(height, self.pos.y));
+        let dirs = Direction::get_orthogonal_dirs();
+
+        let total = (width * height) as f64;
+        let mut floors = (0..height)
+            .flat_map(|y| (0..width).map(move |x| (x, y)))
+            .filter(|&pos| matches!(level.get_tile_with_tuple(pos), Ok(&Tile::Floor(_))))
+            .count();
+        let mut carve = |level: &mut GridLevel<Tile>, pos: (usize, usize)| {
+            let tile = &mut level.tiles[pos];
+            if !matches!(*tile, Tile::Floor(_)) {
+                *tile = Tile::Floor(0);
+                floors += 1;
+            }
+            floors as f64 / total >= self.target_floor
+        };
+
+        let mut walkers: Vec<Walker> = (0..self.walkers)
+            .map(|_| Walker {
+                pos: start,
+                dir: random_dir(rng),
+            })
+            .collect();
+        if carve(level, start) {
+            return;
+        }
+        let mut steps = 0;
+        while steps < self.steps {
+            for walker in &mut walkers {
+                if rng.gen::<f64>() < self.turn_chance {
+                    walker.dir = random_dir(rng);
+                }
+                let next = dirs[walker.dir].offset(walker.pos).filter(|&(x, y)| {
+                    x < width && y < height && inner(width, x) && inner(height, y)
+                });
+                match next {
+                    Some(next) => walker.pos = next,
+                    // Walkers that run into the edge pick a new random direction.
+                    None => walker.dir = random_dir(rng),
+                }
+                steps += 1;
+                if carve(level, walker.pos) || steps >= self.steps {
+                    return;
+                }
+            }
+
+            let mut spawned = Vec::new();
+            for walker in &walkers {
+                if walkers.len() + spawned.len() < self.max_walkers
+                    && rng.gen::<f64>() < self.spawn_chance
+                {
+                    spawned.push(Walker {
+                        pos: walker.pos,
+                        dir: random_dir(rng),
+                    });
+                }
+            }
+            walkers.extend(spawned);
+            let mut i = 0;
+            while i < walkers.len() {
+                if walkers.len() > 1 && rng.gen::<f64>() < self.death_chance {
+                    walkers.swap_remove(i);
+                } else {
+                    i += 1;
+                }
+            }
+        }
+    }
+}
+
+fn random_dir<R: Rng + ?Sized>(rng: &mut R) -> usize {
+    random_index(rng, Direction::get_orthogonal_dirs().len())
+}
+
+impl GridGenerator<Tile> for WalkerGen {
+    fn generate(
+        &mut self,
+        level: &mut GridLevel<Tile>,
+        rng: &mut dyn RngCore,
+    ) -> Result<(), Error> {
+        self.generate_with(level, rng);
+        Ok(())
+    }
+}
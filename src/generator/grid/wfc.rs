@@ -0,0 +1,605 @@
+use std::cmp;
+
+use rand::{rngs::OsRng, Rng, RngCore, SeedableRng};
+use rand_xorshift::XorShiftRng;
+
+use generator::GridGenerator;
+use level::GridLevel;
+use util::{Error, Grid};
+
+/// How `WfcGen` learns from its sample.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WfcMode {
+    /// Learns every `N`×`N` pattern of the sample. Patterns in the output overlap, so every
+    /// `N`×`N` area of the output appears somewhere in the sample.
+    Overlapping(usize),
+    /// Learns which tiles appear next to each other in the sample. Every pair of orthogonally
+    /// adjacent tiles in the output appears somewhere in the sample.
+    Adjacency,
+}
+
+/// Wave function collapse: generates levels of any size in the style of a small sample level.
+///
+/// The output is built by repeatedly fixing the least certain position to one of its possible
+/// patterns and propagating what that rules out. If that leads to a contradiction, the generator
+/// undoes its latest decisions and tries something else, and if it has to backtrack too often,
+/// it starts over.
+pub struct WfcGen<T> {
+    sample: GridLevel<T>,
+    mode: WfcMode,
+    periodic_input: bool,
+    periodic_output: bool,
+    symmetry: usize,
+    max_backtracks: usize,
+    attempts: usize,
+    pinned: Vec<((usize, usize), T)>,
+    rand: XorShiftRng,
+}
+
+impl<T: Clone + PartialEq> WfcGen<T> {
+    /// Creates a `WfcGen` that learns from `sample` without wrapping around its edges, doesn't
+    /// augment it and doesn't wrap the output. It backtracks up to 1000 times per attempt and
+    /// makes up to 10 attempts.
+    pub fn new(sample: &GridLevel<T>, mode: WfcMode) -> WfcGen<T> {
+        WfcGen::from_rng(sample, mode, &mut OsRng::new().unwrap())
+    }
+
+    pub fn with_seed(sample: &GridLevel<T>, mode: WfcMode, seed: u64) -> WfcGen<T> {
+        WfcGen::from_rng(sample, mode, &mut XorShiftRng::seed_from_u64(seed))
+    }
+
+    pub fn from_rng<R: Rng>(sample: &GridLevel<T>, mode: WfcMode, rng: &mut R) -> WfcGen<T> {
+        WfcGen {
+            sample: sample.clone(),
+            mode,
+            periodic_input: false,
+            periodic_output: false,
+            symmetry: 1,
+            max_backtracks: 1000,
+            attempts: 10,
+            pinned: Vec::new(),
+            rand: XorShiftRng::from_rng(rng).unwrap(),
+        }
+    }
+
+    /// If set, the sample wraps around at its edges when patterns are learned.
+    pub fn with_periodic_input(mut self, periodic: bool) -> WfcGen<T> {
+        self.periodic_input = periodic;
+        self
+    }
+
+    /// If set, the output wraps around at its edges, so it can be tiled seamlessly.
+    pub fn with_periodic_output(mut self, periodic: bool) -> WfcGen<T> {
+        self.periodic_output = periodic;
+        self
+    }
+
+    /// Also learns from rotated and mirrored copies of the sample. `symmetry` is the number of
+    /// copies between 1 and 8: the sample itself, its mirror image, the sample rotated by 90
+    /// degrees, its mirror image and so on.
+    pub fn with_symmetry(mut self, symmetry: usize) -> WfcGen<T> {
+        self.symmetry = symmetry.clamp(1, 8);
+        self
+    }
+
+    /// Sets how often a single attempt may undo a decision before the generator starts over,
+    /// and how many attempts it makes before giving up.
+    pub fn with_retries(mut self, max_backtracks: usize, attempts: usize) -> WfcGen<T> {
+        self.max_backtracks = max_backtracks;
+        self.attempts = cmp::max(attempts, 1);
+        self
+    }
+
+    /// Fixes the output tile at `(x, y)` to `tile` before anything else is generated.
+    pub fn pin(mut self, x: usize, y: usize, tile: T) -> WfcGen<T> {
+        self.pinned.push(((x, y), tile));
+        self
+    }
+
+    pub fn reseed(&mut self, seed: u64) {
+        self.rand = XorShiftRng::seed_from_u64(seed);
+    }
+
+    /// Replaces the whole level with generated tiles.
+    pub fn generate(&mut self, level: &mut GridLevel<T>) -> Result<(), Error> {
+        let mut rand = self.rand.clone();
+        let result = self.generate_with(level, &mut rand);
+        self.rand = rand;
+        result
+    }
+
+    fn generate_with<R: Rng + ?Sized>(
+        &self,
+        level: &mut GridLevel<T>,
+        rng: &mut R,
+    ) -> Result<(), Error> {
+        let model = self.learn()?;
+        let n = model.n;
+        let (width, height) = (level.get_width(), level.get_height());
+        if width < n || height < n {
+            return Err(Error::InvalidConfig(format!(
+                "WfcGen: a {}x{} level is too small for {}x{} patterns",
+                width, height, n, n
+            )));
+        }
+        let size = if self.periodic_output {
+            (width, height)
+        } else {
+            (width - n + 1, height - n + 1)
+        };
+        for &((x, y), _) in &self.pinned {
+            if x >= width || y >= height {
+                return Err(Error::InvalidConfig(format!(
+                    "WfcGen: pinned tile ({}, {}) is outside the {}x{} level",
+                    x, y, width, height
+                )));
+            }
+        }
+
+        for _ in 0..self.attempts {
+            let mut wave = Wave::new(&model, size, self.periodic_output);
+            if !wave.ban_unsupported() {
+                return Err(Error::GenerationFailed(format!(
+                    "WfcGen: the sample's patterns can't fill a {}x{} level",
+                    width, height
+                )));
+            }
+            if !self.apply_pins(&model, &mut wave) {
+                return Err(Error::GenerationFailed(
+                    "WfcGen: the pinned tiles contradict the sample".to_owned(),
+                ));
+            }
+            if let Some(chosen) = wave.run(rng, self.max_backtracks) {
+                for y in 0..height {
+                    for x in 0..width {
+                        let (px, py) = (cmp::min(x, size.0 - 1), cmp::min(y, size.1 - 1));
+                        let pattern = &model.patterns[chosen[px + py * size.0]];
+                        level.tiles[(x, y)] = pattern[(x - px) + (y - py) * n].clone();
+                    }
+                }
+                return Ok(());
+            }
+        }
+        Err(Error::GenerationFailed(format!(
+            "WfcGen: every one of {} attempts ran into a contradiction",
+            self.attempts
+        )))
+    }
+
+    /// Bans every pattern that disagrees with a pinned tile. Returns false on a contradiction.
+    fn apply_pins(&self, model: &Model<T>, wave: &mut Wave) -> bool {
+        let n = model.n;
+        let (width, height) = wave.size;
+        for &((x, y), ref tile) in &self.pinned {
+            for dy in 0..n {
+                for dx in 0..n {
+                    // The wave position whose pattern covers (x, y) at (dx, dy).
+                    let (px, py) = if self.periodic_output {
+                        ((x + width - dx) % width, (y + height - dy) % height)
+                    } else if x >= dx && y >= dy && x - dx < width && y - dy < height {
+                        (x - dx, y - dy)
+                    } else {
+                        continue;
+                    };
+                    let cell = px + py * width;
+                    for (t, pattern) in model.patterns.iter().enumerate() {
+                        if pattern[dx + dy * n] != *tile {
+                            wave.ban(cell, t);
+                        }
+                    }
+                }
+            }
+        }
+        wave.propagate()
+    }
+
+    fn learn(&self) -> Result<Model<T>, Error> {
+        let n = match self.mode {
+            WfcMode::Overlapping(n) => n,
+            WfcMode::Adjacency => 1,
+        };
+        let (width, height) = (self.sample.get_width(), self.sample.get_height());
+        if n == 0 || width < n || height < n {
+            return Err(Error::InvalidConfig(format!(
+                "WfcGen: can't learn {}x{} patterns from a {}x{} sample",
+                n, n, width, height
+            )));
+        }
+
+        let mut model = Model {
+            n,
+            patterns: Vec::new(),
+            weights: Vec::new(),
+            propagator: [Vec::new(), Vec::new(), Vec::new(), Vec::new()],
+        };
+        let samples = variants(&self.sample.tiles, self.symmetry);
+        for sample in &samples {
+            let (width, height) = (sample.get_width(), sample.get_height());
+            let (max_x, max_y) = if self.periodic_input {
+                (width, height)
+            } else {
+                (width - n + 1, height - n + 1)
+            };
+            for y in 0..max_y {
+                for x in 0..max_x {
+                    let mut pattern = Vec::with_capacity(n * n);
+                    for dy in 0..n {
+                        for dx in 0..n {
+                            pattern.push(sample[((x + dx) % width, (y + dy) % height)].clone());
+                        }
+                    }
+                    model.add(pattern);
+                }
+            }
+        }
+
+        let count = model.patterns.len();
+        for propagator in &mut model.propagator {
+            *propagator = vec![Vec::new(); count];
+        }
+        match self.mode {
+            WfcMode::Overlapping(_) => {
+                for d in 0..4 {
+                    for a in 0..count {
+                        for b in 0..count {
+                            if agrees(&model.patterns[a], &model.patterns[b], d, n) {
+                                model.propagator[d][a].push(b);
+                            }
+                        }
+                    }
+                }
+            }
+            WfcMode::Adjacency => {
+                for sample in &samples {
+                    let (width, height) = (sample.get_width(), sample.get_height());
+                    for y in 0..height {
+                        for x in 0..width {
+                            let a = model.find(&sample[(x, y)]);
+                            for d in 0..4 {
+                                let (nx, ny) = (x as isize + DX[d], y as isize + DY[d]);
+                                let inside = nx >= 0
+                                    && ny >= 0
+                                    && (nx as usize) < width
+                                    && (ny as usize) < height;
+                                if !inside && !self.periodic_input {
+                                    continue;
+                                }
+                                let nx = (nx + width as isize) as usize % width;
+                                let ny = (ny + height as isize) as usize % height;
+                                let b = model.find(&sample[(nx, ny)]);
+                                if !model.propagator[d][a].contains(&b) {
+                                    model.propagator[d][a].push(b);
+                                    model.propagator[opposite(d)][b].push(a);
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        Ok(model)
+    }
+}
+
+impl<T: Clone + PartialEq> GridGenerator<T> for WfcGen<T> {
+    fn generate(&mut self, level: &mut GridLevel<T>, rng: &mut dyn RngCore) -> Result<(), Error> {
+        self.generate_with(level, rng)
+    }
+}
+
+// Directions in the order left, down, right, up.
+const DX: [isize; 4] = [-1, 0, 1, 0];
+const DY: [isize; 4] = [0, 1, 0, -1];
+
+fn opposite(d: usize) -> usize {
+    (d + 2) % 4
+}
+
+/// Returns true if pattern `b` can be placed one step in direction `d` from pattern `a`.
+fn agrees<T: PartialEq>(a: &[T], b: &[T], d: usize, n: usize) -> bool {
+    let (dx, dy) = (DX[d], DY[d]);
+    let n = n as isize;
+    for y in cmp::max(0, dy)..cmp::min(n, n + dy) {
+        for x in cmp::max(0, dx)..cmp::min(n, n + dx) {
+            if a[(x + y * n) as usize] != b[((x - dx) + (y - dy) * n) as usize] {
+                return false;
+            }
+        }
+    }
+    true
+}
+
+/// The first `count` of the sample's rotations and mirror images.
+fn variants<T: Clone>(sample: &Grid<T>, count: usize) -> Vec<Grid<T>> {
+    let mut variants = Vec::with_capacity(8);
+    let mut rotated = sample.clone();
+    for _ in 0..4 {
+        let next = transform(&rotated, true);
+        variants.push(transform(&rotated, false));
+        variants.push(rotated);
+        rotated = next;
+    }
+    // Every rotation comes before its mirror image.
+    for pair in variants.chunks_mut(2) {
+        pair.swap(0, 1);
+    }
+    variants.truncate(count);
+    variants
+}
+
+/// Mirrors `grid` along the y axis or rotates it by 90 degrees.
+fn transform<T: Clone>(grid: &Grid<T>, rotate: bool) -> Grid<T> {
+    let (width, height) = (grid.get_width(), grid.get_height());
+    let (new_width, new_height) = if rotate {
+        (height, width)
+    } else {
+        (width, height)
+    };
+    let mut data = Vec::with_capacity(width * height);
+    for y in 0..new_height {
+        for x in 0..new_width {
+            let from = if rotate {
+                (width - 1 - y, x)
+            } else {
+                (width - 1 - x, y)
+            };
+            data.push(grid[from].clone());
+        }
+    }
+    let mut result = Grid::new_filled_with(grid[(0, 0)].clone(), new_width, new_height);
+    result.data = data;
+    result
+}
+
+struct Model<T> {
+    n: usize,
+    patterns: Vec<Vec<T>>,
+    weights: Vec<f64>,
+    /// `propagator[d][a]` lists the patterns that may be one step in direction `d` of `a`.
+    propagator: [Vec<Vec<usize>>; 4],
+}
+
+impl<T: PartialEq> Model<T> {
+    fn add(&mut self, pattern: Vec<T>) {
+        match self.patterns.iter().position(|p| *p == pattern) {
+            Some(i) => self.weights[i] += 1.,
+            None => {
+                self.patterns.push(pattern);
+                self.weights.push(1.);
+            }
+        }
+    }
+
+    fn find(&self, tile: &T) -> usize {
+        self.patterns.iter().position(|p| p[0] == *tile).unwrap()
+    }
+}
+
+/// A change to the wave that can be undone when backtracking.
+enum Change {
+    Ban { index: usize, compatible: [i32; 4] },
+    Decrement { index: usize, d: usize },
+}
+
+struct Decision {
+    trail_len: usize,
+    cell: usize,
+    pattern: usize,
+}
+
+/// The solver state: which patterns are still possible at every position.
+struct Wave<'a> {
+    propagator: &'a [Vec<Vec<usize>>; 4],
+    weights: &'a [f64],
+    log_weights: Vec<f64>,
+    patterns: usize,
+    size: (usize, usize),
+    periodic: bool,
+    allowed: Vec<bool>,
+    /// For every position and pattern, how many patterns of the neighbour in each direction
+    /// still allow it.
+    compatible: Vec<[i32; 4]>,
+    counts: Vec<usize>,
+    sum_weights: Vec<f64>,
+    sum_log_weights: Vec<f64>,
+    contradiction: bool,
+    stack: Vec<(usize, usize)>,
+    trail: Vec<Change>,
+}
+
+impl<'a> Wave<'a> {
+    fn new<T>(model: &'a Model<T>, size: (usize, usize), periodic: bool) -> Wave<'a> {
+        let patterns = model.patterns.len();
+        let cells = size.0 * size.1;
+        let log_weights: Vec<f64> = model.weights.iter().map(|w| w * w.ln()).collect();
+        let initial: Vec<[i32; 4]> = (0..patterns)
+            .map(|t| {
+                let support = |d| model.propagator[opposite(d)][t].len() as i32;
+                [support(0), support(1), support(2), support(3)]
+            })
+            .collect();
+        Wave {
+            propagator: &model.propagator,
+            weights: &model.weights,
+            patterns,
+            size,
+            periodic,
+            allowed: vec![true; cells * patterns],
+            compatible: (0..cells).flat_map(|_| initial.iter().cloned()).collect(),
+            counts: vec![patterns; cells],
+            sum_weights: vec![model.weights.iter().sum(); cells],
+            sum_log_weights: vec![log_weights.iter().sum(); cells],
+            log_weights,
+            contradiction: false,
+            stack: Vec::new(),
+            trail: Vec::new(),
+        }
+    }
+
+    /// Collapses the whole wave and returns the pattern chosen for every position,
+    /// or `None` if it runs into a contradiction it can't backtrack out of.
+    fn run<R: Rng + ?Sized>(&mut self, rng: &mut R, max_backtracks: usize) -> Option<Vec<usize>> {
+        let mut decisions: Vec<Decision> = Vec::new();
+        let mut backtracks = 0;
+        while let Some(cell) = self.next_cell(rng) {
+            let pattern = self.choose(cell, rng);
+            decisions.push(Decision {
+                trail_len: self.trail.len(),
+                cell,
+                pattern,
+            });
+            for t in 0..self.patterns {
+                if t != pattern {
+                    self.ban(cell, t);
+                }
+            }
+            // Undo decisions until one of them can be made differently.
+            while !self.propagate() {
+                let decision = decisions.pop()?;
+                backtracks += 1;
+                if backtracks > max_backtracks {
+                    return None;
+                }
+                self.undo(decision.trail_len);
+                self.ban(decision.cell, decision.pattern);
+            }
+        }
+        if self.contradiction {
+            return None;
+        }
+        Some(
+            (0..self.counts.len())
+                .map(|cell| {
+                    (0..self.patterns)
+                        .find(|&t| self.allowed[cell * self.patterns + t])
+                        .unwrap()
+                })
+                .collect(),
+        )
+    }
+
+    /// Bans the patterns that can't have a neighbour in a direction where there is one.
+    /// Returns false on a contradiction.
+    fn ban_unsupported(&mut self) -> bool {
+        let (width, height) = self.size;
+        for cell in 0..self.counts.len() {
+            let (x, y) = (cell % width, cell / width);
+            for t in 0..self.patterns {
+                for d in 0..4 {
+                    let has_neighbour = self.periodic
+                        || match d {
+                            0 => x > 0,
+                            1 => y + 1 < height,
+                            2 => x + 1 < width,
+                            _ => y > 0,
+                        };
+                    // The neighbour in direction `d` supports `t` from the opposite side.
+                    if has_neighbour && self.compatible[cell * self.patterns + t][opposite(d)] == 0
+                    {
+                        self.ban(cell, t);
+                    }
+                }
+            }
+        }
+        self.propagate()
+    }
+
+    /// The undecided position with the lowest entropy, with a bit of noise to break ties.
+    fn next_cell<R: Rng + ?Sized>(&self, rng: &mut R) -> Option<usize> {
+        let mut best = None;
+        let mut min = f64::MAX;
+        for cell in 0..self.counts.len() {
+            if self.counts[cell] <= 1 {
+                continue;
+            }
+            let sum = self.sum_weights[cell];
+            let entropy = sum.ln() - self.sum_log_weights[cell] / sum + 1e-6 * rng.gen::<f64>();
+            if entropy < min {
+                min = entropy;
+                best = Some(cell);
+            }
+        }
+        best
+    }
+
+    fn choose<R: Rng + ?Sized>(&self, cell: usize, rng: &mut R) -> usize {
+        let allowed = |t: &usize| self.allowed[cell * self.patterns + t];
+        let mut r = rng.gen::<f64>() * self.sum_weights[cell];
+        let mut last = 0;
+        for t in (0..self.patterns).filter(allowed) {
+            r -= self.weights[t];
+            last = t;
+            if r < 0. {
+                break;
+            }
+        }
+        last
+    }
+
+    fn ban(&mut self, cell: usize, t: usize) {
+        let index = cell * self.patterns + t;
+        if !self.allowed[index] {
+            return;
+        }
+        self.allowed[index] = false;
+        self.trail.push(Change::Ban {
+            index,
+            compatible: self.compatible[index],
+        });
+        self.compatible[index] = [0; 4];
+        self.stack.push((cell, t));
+        self.counts[cell] -= 1;
+        self.sum_weights[cell] -= self.weights[t];
+        self.sum_log_weights[cell] -= self.log_weights[t];
+        if self.counts[cell] == 0 {
+            self.contradiction = true;
+        }
+    }
+
+    /// Bans everything that isn't supported by its neighbours any more.
+    /// Returns false on a contradiction.
+    fn propagate(&mut self) -> bool {
+        while let Some((cell, t1)) = self.stack.pop() {
+            let (width, height) = self.size;
+            let (x, y) = ((cell % width) as isize, (cell / width) as isize);
+            for d in 0..4 {
+                let (mut nx, mut ny) = (x + DX[d], y + DY[d]);
+                if self.periodic {
+                    nx = (nx + width as isize) % width as isize;
+                    ny = (ny + height as isize) % height as isize;
+                } else if nx < 0 || ny < 0 || nx >= width as isize || ny >= height as isize {
+                    continue;
+                }
+                let neighbour = nx as usize + ny as usize * width;
+                let propagator = self.propagator;
+                for &t2 in &propagator[d][t1] {
+                    let index = neighbour * self.patterns + t2;
+                    self.compatible[index][d] -= 1;
+                    self.trail.push(Change::Decrement { index, d });
+                    if self.compatible[index][d] == 0 {
+                        self.ban(neighbour, t2);
+                    }
+                }
+            }
+        }
+        !self.contradiction
+    }
+
+    fn undo(&mut self, trail_len: usize) {
+        while self.trail.len() > trail_len {
+            match self.trail.pop().unwrap() {
+                Change::Ban { index, compatible } => {
+                    let (cell, t) = (index / self.patterns, index % self.patterns);
+                    self.allowed[index] = true;
+                    self.compatible[index] = compatible;
+                    self.counts[cell] += 1;
+                    self.sum_weights[cell] += self.weights[t];
+                    self.sum_log_weights[cell] += self.log_weights[t];
+                }
+                Change::Decrement { index, d } => self.compatible[index][d] += 1,
+            }
+        }
+        self.stack.clear();
+        self.contradiction = false;
+    }
+}
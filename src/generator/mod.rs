@@ -1,9 +1,40 @@
 mod grid;
 mod unaligned;
 
-pub use self::grid::dungeon::DungeonGen;
+use rand::RngCore;
+
+use level::GridLevel;
+use util::Error;
+
+pub use self::grid::autotile::{autotile_index, AutotileMask, Autotiler};
+pub use self::grid::bsp::BspGen;
+pub use self::grid::cave::{CaveGen, CaveRules};
+pub use self::grid::connector::RoomConnector;
+pub use self::grid::dungeon::{DungeonGen, Sparseness};
 pub use self::grid::faction::FactionGen;
-pub use self::grid::maze::MazeGen;
-pub use self::grid::room::RoomGen;
+pub use self::grid::maze::{MazeAlgorithm, MazeGen};
+pub use self::grid::pipeline::{Pipeline, PipelineReport, StepOutcome, StepReport};
+pub use self::grid::prefab::{Prefab, PrefabGen};
+pub use self::grid::room::{Room, RoomGen};
+pub use self::grid::stairs::{StairDistance, StairPlacer};
+pub use self::grid::walker::WalkerGen;
+pub use self::grid::wfc::{WfcGen, WfcMode};
 
 pub use self::unaligned::forest::generate_forest;
+
+/// A generator that modifies a `GridLevel` in place.
+///
+/// All randomness is drawn from the given `rng`, so the same generator, level and rng state
+/// always give the same result.
+pub trait GridGenerator<T> {
+    fn generate(&mut self, level: &mut GridLevel<T>, rng: &mut dyn RngCore) -> Result<(), Error>;
+}
+
+impl<T, F> GridGenerator<T> for F
+where
+    F: FnMut(&mut GridLevel<T>, &mut dyn RngCore) -> Result<(), Error>,
+{
+    fn generate(&mut self, level: &mut GridLevel<T>, rng: &mut dyn RngCore) -> Result<(), Error> {
+        self(level, rng)
+    }
+}
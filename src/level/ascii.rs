@@ -0,0 +1,188 @@
+use std::fmt;
+use std::mem;
+
+use level::GridLevel;
+use tile::Tile;
+use util::{Error, Grid};
+
+/// A tile variant's constructor, like `Tile::Wall`.
+pub type TileKind = fn(usize) -> Tile;
+
+/// A mapping between tiles and characters for `GridLevel::to_ascii` and
+/// `GridLevel::from_ascii`.
+///
+/// Tiles are looked up by their exact value first, then as a digit if digits are enabled for
+/// their kind, and last by their kind alone. Parsing a kind's character gives a tile with a
+/// payload of 0.
+#[derive(Clone)]
+pub struct CharMap {
+    exact: Vec<(Tile, char)>,
+    kinds: Vec<(TileKind, char)>,
+    digits: Option<TileKind>,
+    unknown: char,
+}
+
+impl CharMap {
+    /// Creates a `CharMap` without any characters. Tiles without a character are rendered
+    /// as `?`.
+    pub fn new() -> CharMap {
+        CharMap {
+            exact: Vec::new(),
+            kinds: Vec::new(),
+            digits: None,
+            unknown: '?',
+        }
+    }
+
+    /// Maps exactly `tile` to `c`, for example `Tile::Floor(1)` to `~`.
+    pub fn with_tile(mut self, tile: Tile, c: char) -> CharMap {
+        self.exact.retain(|&(ref t, old)| *t != tile && old != c);
+        self.exact.push((tile, c));
+        self
+    }
+
+    /// Maps every tile of a kind, like `Tile::Wall`, to `c`.
+    pub fn with_kind(mut self, kind: TileKind, c: char) -> CharMap {
+        self.kinds
+            .retain(|&(k, old)| !same_kind(&k(0), &kind(0)) && old != c);
+        self.kinds.push((kind, c));
+        self
+    }
+
+    /// Renders tiles of a kind with a payload from 1 to 9 as that payload's digit, and parses
+    /// all ten digits. A payload of 0 is still rendered with the kind's character if it has
+    /// one, so that levels round trip through both.
+    pub fn with_digits(mut self, kind: TileKind) -> CharMap {
+        self.digits = Some(kind);
+        self
+    }
+
+    /// Sets the character for tiles that have no character of their own.
+    pub fn with_unknown(mut self, c: char) -> CharMap {
+        self.unknown = c;
+        self
+    }
+
+    /// The character for `tile` or `None` if it has none.
+    pub fn char_for(&self, tile: &Tile) -> Option<char> {
+        if let Some(&(_, c)) = self.exact.iter().find(|(t, _)| t == tile) {
+            return Some(c);
+        }
+        let kind_char = self
+            .kinds
+            .iter()
+            .find(|&&(kind, _)| same_kind(&kind(0), tile))
+            .map(|&(_, c)| c);
+        if let Some(kind) = self.digits {
+            let payload = payload(tile);
+            if same_kind(&kind(0), tile) && payload < 10 && !(payload == 0 && kind_char.is_some()) {
+                return ::std::char::from_digit(payload as u32, 10);
+            }
+        }
+        kind_char
+    }
+
+    /// The tile for `c` or `None` if no tile maps to it.
+    pub fn tile_for(&self, c: char) -> Option<Tile> {
+        if let Some((tile, _)) = self.exact.iter().find(|&&(_, d)| d == c) {
+            return Some(tile.clone());
+        }
+        if let (Some(kind), Some(digit)) = (self.digits, c.to_digit(10)) {
+            return Some(kind(digit as usize));
+        }
+        self.kinds
+            .iter()
+            .find(|&&(_, d)| d == c)
+            .map(|&(kind, _)| kind(0))
+    }
+}
+
+/// `#` for walls, `.` for floors, a space for void, `+` for doors and `<` and `>` for stairs
+/// up and down.
+impl Default for CharMap {
+    fn default() -> CharMap {
+        CharMap::new()
+            .with_kind(Tile::Wall, '#')
+            .with_kind(Tile::Floor, '.')
+            .with_kind(Tile::Void, ' ')
+            .with_kind(Tile::Door, '+')
+            .with_kind(Tile::StairsUp, '<')
+            .with_kind(Tile::StairsDown, '>')
+    }
+}
+
+pub(crate) fn same_kind(a: &Tile, b: &Tile) -> bool {
+    mem::discriminant(a) == mem::discriminant(b)
+}
+
+pub(crate) fn payload(tile: &Tile) -> usize {
+    match *tile {
+        Tile::Wall(n)
+        | Tile::Floor(n)
+        | Tile::Void(n)
+        | Tile::Door(n)
+        | Tile::StairsUp(n)
+        | Tile::StairsDown(n) => n,
+    }
+}
+
+impl GridLevel<Tile> {
+    /// Renders the level with one line per row, starting with y = 0. Every line ends with a
+    /// newline.
+    pub fn to_ascii(&self, map: &CharMap) -> String {
+        let mut text = String::with_capacity((self.get_width() + 1) * self.get_height());
+        for y in 0..self.get_height() {
+            for x in 0..self.get_width() {
+                text.push(map.char_for(&self.tiles[(x, y)]).unwrap_or(map.unknown));
+            }
+            text.push('\n');
+        }
+        text
+    }
+
+    /// Parses a level in the format written by `to_ascii`. Every line has to be equally long
+    /// and may only contain characters of `map`. A final newline is optional.
+    pub fn from_ascii(text: &str, map: &CharMap) -> Result<GridLevel<Tile>, Error> {
+        let mut width = None;
+        let mut data = Vec::new();
+        for (y, line) in text.lines().enumerate() {
+            let mut length = 0;
+            for (x, c) in line.chars().enumerate() {
+                data.push(map.tile_for(c).ok_or_else(|| {
+                    Error::Format(format!(
+                        "unknown character {:?} in line {}, column {}",
+                        c,
+                        y + 1,
+                        x + 1
+                    ))
+                })?);
+                length += 1;
+            }
+            match width {
+                None => width = Some(length),
+                Some(width) if width != length => {
+                    return Err(Error::Format(format!(
+                        "line {} is {} characters long but line 1 is {}",
+                        y + 1,
+                        length,
+                        width
+                    )))
+                }
+                Some(_) => {}
+            }
+        }
+        match width {
+            Some(width) if width > 0 => Ok(GridLevel {
+                tiles: Grid::from_data(width, data).map_err(Error::Format)?,
+            }),
+            _ => Err(Error::Format("the level has no tiles".to_owned())),
+        }
+    }
+}
+
+/// Draws the level with the default `CharMap`.
+impl fmt::Display for GridLevel<Tile> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.to_ascii(&CharMap::default()))
+    }
+}
@@ -0,0 +1,219 @@
+use level::GridLevel;
+
+/// Which tiles `field_of_view` counts as visible.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FovMode {
+    /// Transparent tiles are only visible if their center can be seen. Then `a` sees `b`
+    /// exactly when `b` sees `a`. Opaque tiles are visible if any part of them can be seen.
+    Symmetric,
+    /// Every tile that can be seen in part is visible.
+    Permissive,
+}
+
+/// One of the four 90 degree sectors around the origin. `depth` goes away from the origin,
+/// `col` along the row.
+#[derive(Clone, Copy)]
+enum Quadrant {
+    North,
+    East,
+    South,
+    West,
+}
+
+impl Quadrant {
+    fn transform(self, (x, y): (i64, i64), depth: i64, col: i64) -> (i64, i64) {
+        match self {
+            Quadrant::North => (x + col, y - depth),
+            Quadrant::South => (x + col, y + depth),
+            Quadrant::East => (x + depth, y + col),
+            Quadrant::West => (x - depth, y + col),
+        }
+    }
+}
+
+/// A slope as a fraction with a positive denominator.
+#[derive(Clone, Copy)]
+struct Slope {
+    num: i64,
+    den: i64,
+}
+
+impl Slope {
+    /// The slope through the edge of the tile at `col` that is closest to the start of the row.
+    fn of_tile(depth: i64, col: i64) -> Slope {
+        Slope {
+            num: 2 * col - 1,
+            den: 2 * depth,
+        }
+    }
+
+    /// `depth * self`, rounded to the nearest integer with ties rounded up.
+    fn round_up(self, depth: i64) -> i64 {
+        (2 * depth * self.num + self.den).div_euclid(2 * self.den)
+    }
+
+    /// `depth * self`, rounded to the nearest integer with ties rounded down.
+    fn round_down(self, depth: i64) -> i64 {
+        -(self.den - 2 * depth * self.num).div_euclid(2 * self.den)
+    }
+}
+
+struct Shadowcast<'a, T: 'a, F> {
+    level: &'a GridLevel<T>,
+    origin: (i64, i64),
+    radius: i64,
+    mode: FovMode,
+    opaque: F,
+    visible: GridLevel<bool>,
+}
+
+impl<'a, T, F: Fn(&T) -> bool> Shadowcast<'a, T, F> {
+    /// Whether `pos` is opaque. Positions outside the level are.
+    fn blocks(&self, (x, y): (i64, i64)) -> bool {
+        if x < 0 || y < 0 {
+            return true;
+        }
+        self.level
+            .get_tile(x as usize, y as usize)
+            .map_or(true, |tile| (self.opaque)(tile))
+    }
+
+    fn reveal(&mut self, (x, y): (i64, i64), depth: i64, col: i64) {
+        if depth * depth + col * col > self.radius * self.radius {
+            return;
+        }
+        if let Ok(visible) = self.visible.get_mut_tile(x as usize, y as usize) {
+            *visible = true;
+        }
+    }
+
+    fn scan(&mut self, quadrant: Quadrant, depth: i64, mut start: Slope, end: Slope) {
+        if depth > self.radius {
+            return;
+        }
+        let mut previous = None;
+        for col in start.round_up(depth)..=end.round_down(depth) {
+            let pos = quadrant.transform(self.origin, depth, col);
+            let wall = self.blocks(pos);
+            let symmetric =
+                col * start.den >= depth * start.num && col * end.den <= depth * end.num;
+            if wall || symmetric || self.mode == FovMode::Permissive {
+                self.reveal(pos, depth, col);
+            }
+            if previous == Some(true) && !wall {
+                start = Slope::of_tile(depth, col);
+            }
+            if previous == Some(false) && wall {
+                self.scan(quadrant, depth + 1, start, Slope::of_tile(depth, col));
+            }
+            previous = Some(wall);
+        }
+        if previous == Some(false) {
+            self.scan(quadrant, depth + 1, start, end);
+        }
+    }
+}
+
+/// Computes which tiles can be seen from `origin` with recursive shadowcasting. Tiles for
+/// which `opaque` returns true block the view and tiles farther away than `radius` aren't
+/// visible. The origin is always visible, unless it is outside of the level.
+pub fn field_of_view<T, F>(
+    level: &GridLevel<T>,
+    origin: (usize, usize),
+    radius: usize,
+    mode: FovMode,
+    opaque: F,
+) -> GridLevel<bool>
+where
+    F: Fn(&T) -> bool,
+{
+    let (width, height) = (level.get_width(), level.get_height());
+    let mut fov = Shadowcast {
+        level,
+        origin: (origin.0 as i64, origin.1 as i64),
+        // Nothing is farther away than the level's width plus its height.
+        radius: radius.min(width + height) as i64,
+        mode,
+        opaque,
+        visible: GridLevel::new_filled_with(false, width, height),
+    };
+    if let Ok(visible) = fov.visible.get_mut_tile(origin.0, origin.1) {
+        *visible = true;
+        let (start, end) = (Slope { num: -1, den: 1 }, Slope { num: 1, den: 1 });
+        for &quadrant in &[
+            Quadrant::North,
+            Quadrant::East,
+            Quadrant::South,
+            Quadrant::West,
+        ] {
+            fov.scan(quadrant, 1, start, end);
+        }
+    }
+    fov.visible
+}
+
+/// The positions of the tiles that `field_of_view` finds visible, scanning rows from y = 0.
+pub fn visible_cells<T, F>(
+    level: &GridLevel<T>,
+    origin: (usize, usize),
+    radius: usize,
+    mode: FovMode,
+    opaque: F,
+) -> impl Iterator<Item = (usize, usize)>
+where
+    F: Fn(&T) -> bool,
+{
+    let visible = field_of_view(level, origin, radius, mode, opaque);
+    let width = visible.get_width();
+    let cells: Vec<bool> = visible.tiles.data;
+    cells
+        .into_iter()
+        .enumerate()
+        .filter(|&(_, visible)| visible)
+        .map(move |(i, _)| (i % width, i / width))
+}
+
+/// The positions on the Bresenham line from `from` to `to`, including both ends.
+pub fn line(from: (usize, usize), to: (usize, usize)) -> Vec<(usize, usize)> {
+    let (x0, y0) = (from.0 as i64, from.1 as i64);
+    let (x1, y1) = (to.0 as i64, to.1 as i64);
+    let (dx, dy) = ((x1 - x0).abs(), -(y1 - y0).abs());
+    let (sx, sy) = ((x1 - x0).signum(), (y1 - y0).signum());
+    let (mut x, mut y, mut error) = (x0, y0, dx + dy);
+    let mut points = vec![from];
+    while (x, y) != (x1, y1) {
+        let doubled = 2 * error;
+        if doubled >= dy {
+            error += dy;
+            x += sx;
+        }
+        if doubled <= dx {
+            error += dx;
+            y += sy;
+        }
+        points.push((x as usize, y as usize));
+    }
+    points
+}
+
+/// Returns true if no tile on the Bresenham line between `from` and `to` is opaque. The two
+/// ends themselves may be opaque. False if either end is outside of the level.
+pub fn line_of_sight<T, F>(
+    level: &GridLevel<T>,
+    from: (usize, usize),
+    to: (usize, usize),
+    opaque: F,
+) -> bool
+where
+    F: Fn(&T) -> bool,
+{
+    if level.get_tile_with_tuple(from).is_err() || level.get_tile_with_tuple(to).is_err() {
+        return false;
+    }
+    let points = line(from, to);
+    points
+        .iter()
+        .skip(1)
+        .take(points.len().saturating_sub(2))
+        .all(|&pos| !opaque(&level.tiles[pos]))
+}
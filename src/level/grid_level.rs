@@ -1,9 +1,13 @@
+use rand::{seq::SliceRandom, Rng};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 use std::default::Default;
 use tile::Tile;
-use util::{Error, Grid};
+use util::{Direction, Error, Grid, Neighbourhood};
 use Vector;
 
-#[derive(Clone)]
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(transparent))]
 pub struct GridLevel<T> {
     pub(crate) tiles: Grid<T>,
 }
@@ -18,11 +22,8 @@ impl<T> GridLevel<T> {
     }
 
     pub fn get_tile(&self, x: usize, y: usize) -> Result<&T, Error> {
-        if x < self.get_width() && y < self.get_height() {
-            Ok(&self.tiles[(x, y)])
-        } else {
-            Err(Error::IndexOutOfBounds)
-        }
+        self.check_bounds(x, y)?;
+        Ok(&self.tiles[(x, y)])
     }
 
     pub fn get_tile_with_vec(&self, pos: &Vector<usize>) -> Result<&T, Error> {
@@ -34,11 +35,8 @@ impl<T> GridLevel<T> {
     }
 
     pub fn get_mut_tile(&mut self, x: usize, y: usize) -> Result<&mut T, Error> {
-        if x < self.get_width() && y < self.get_height() {
-            Ok(&mut self.tiles[(x, y)])
-        } else {
-            Err(Error::IndexOutOfBounds)
-        }
+        self.check_bounds(x, y)?;
+        Ok(&mut self.tiles[(x, y)])
     }
 
     pub fn get_mut_tile_with_vec(&mut self, pos: &Vector<usize>) -> Result<&mut T, Error> {
@@ -49,6 +47,29 @@ impl<T> GridLevel<T> {
         self.get_mut_tile(x, y)
     }
 
+    fn check_bounds(&self, x: usize, y: usize) -> Result<(), Error> {
+        if x < self.get_width() && y < self.get_height() {
+            Ok(())
+        } else {
+            Err(Error::IndexOutOfBounds {
+                pos: (x, y),
+                size: (self.get_width(), self.get_height()),
+            })
+        }
+    }
+
+    /// Returns a `SizeMismatch` error unless `other` is as large as this level.
+    pub fn check_same_size<U>(&self, other: &GridLevel<U>) -> Result<(), Error> {
+        if self.get_width() == other.get_width() && self.get_height() == other.get_height() {
+            Ok(())
+        } else {
+            Err(Error::SizeMismatch {
+                expected: (self.get_width(), self.get_height()),
+                found: (other.get_width(), other.get_height()),
+            })
+        }
+    }
+
     pub fn apply<F>(&mut self, gen: F) -> &mut GridLevel<T>
     where
         F: FnOnce(&mut GridLevel<T>),
@@ -87,11 +108,9 @@ pub fn fill_dead_end_tiles(level: &mut GridLevel<Tile>) -> bool {
     let mut deadends = Vec::new();
     for y in 0..level.get_height() {
         for x in 0..level.get_width() {
-            if let Ok(n) = level.get_tile(x, y) {
-                if let &Tile::Floor(_) = n {
-                    if is_deadend(level, x, y) {
-                        deadends.push((x, y));
-                    }
+            if let Ok(tile) = level.get_tile(x, y) {
+                if tile.is_passable() && is_deadend(level, x, y) {
+                    deadends.push((x, y));
                 }
             }
         }
@@ -106,8 +125,87 @@ pub fn fill_dead_end_tiles(level: &mut GridLevel<Tile>) -> bool {
     filled_deadend
 }
 
+/// Turns dead ends into loops by knocking down the wall between them and a passage on the
+/// other side of it. About `fraction` of the dead ends, picked at random, are braided. Dead ends
+/// that face another dead end are joined with it where possible, which removes both. Walls are
+/// only knocked down if that doesn't open up the passage to the sides, so dead ends without
+/// a suitable wall stay as they are.
+///
+/// Returns the number of walls that were knocked down.
+pub fn braid_dead_ends<R: Rng + ?Sized>(
+    level: &mut GridLevel<Tile>,
+    fraction: f64,
+    rng: &mut R,
+) -> usize {
+    let mut deadends = Vec::new();
+    for y in 0..level.get_height() {
+        for x in 0..level.get_width() {
+            if level.get_tile(x, y).is_ok_and(Tile::is_passable) && is_deadend(level, x, y) {
+                deadends.push((x, y));
+            }
+        }
+    }
+    deadends.shuffle(rng);
+    let count = (deadends.len() as f64 * fraction).round() as usize;
+
+    let mut braided = 0;
+    for &(x, y) in deadends.iter().take(count) {
+        // An earlier dead end may have been joined with this one.
+        if !is_deadend(level, x, y) {
+            continue;
+        }
+        let mut options = Vec::new();
+        for dir in Direction::get_orthogonal_dirs() {
+            let wall = match dir.offset((x, y)) {
+                Some(wall) => wall,
+                None => continue,
+            };
+            let behind = match dir.offset(wall) {
+                Some(behind) => behind,
+                None => continue,
+            };
+            let knockable = match level.get_tile_with_tuple(wall) {
+                Ok(&Tile::Wall(_)) => passable_neighbours(level, wall) == 2,
+                _ => false,
+            };
+            if knockable && level.get_tile_with_tuple(behind).is_ok_and(Tile::is_passable) {
+                options.push((wall, is_deadend(level, behind.0, behind.1)));
+            }
+        }
+        let joins_deadend = options.iter().any(|&(_, deadend)| deadend);
+        options.retain(|&(_, deadend)| deadend || !joins_deadend);
+        if let Some(&(wall, _)) = options.choose(rng) {
+            level.tiles[wall] = Tile::Floor(0);
+            braided += 1;
+        }
+    }
+    braided
+}
+
+/// Number of orthogonal neighbours of `pos` that are passable.
+pub(crate) fn passable_neighbours(level: &GridLevel<Tile>, pos: (usize, usize)) -> usize {
+    Direction::get_orthogonal_dirs()
+        .iter()
+        .filter_map(|dir| dir.offset(pos))
+        .filter(|&n| level.get_tile_with_tuple(n).is_ok_and(Tile::is_passable))
+        .count()
+}
+
+/// The positions next to `pos` that are inside the level.
+pub(crate) fn neighbours<T>(
+    level: &GridLevel<T>,
+    pos: (usize, usize),
+    neighbourhood: Neighbourhood,
+) -> impl Iterator<Item = (usize, usize)> {
+    let (width, height) = (level.get_width(), level.get_height());
+    neighbourhood
+        .dirs()
+        .iter()
+        .filter_map(move |dir: &Direction| dir.offset(pos))
+        .filter(move |&(x, y)| x < width && y < height)
+}
+
 pub fn is_deadend(level: &GridLevel<Tile>, x: usize, y: usize) -> bool {
-    use util::Direction;
     let mut paths = 0;
     for dir in Direction::get_orthogonal_dirs() {
         let vector = dir.get_vec();
@@ -119,8 +217,10 @@ pub fn is_deadend(level: &GridLevel<Tile>, x: usize, y: usize) -> bool {
             _ => continue,
         };
 
-        if let Ok(&Tile::Floor(_)) = level.get_tile_with_tuple(coord) {
-            paths += 1;
+        if let Ok(tile) = level.get_tile_with_tuple(coord) {
+            if tile.is_passable() {
+                paths += 1;
+            }
         }
     }
     paths < 2
@@ -1,8 +1,32 @@
+mod ascii;
+mod fov;
 mod grid_level;
+mod path;
+#[cfg(feature = "image")]
+mod png;
+mod regions;
+#[cfg(feature = "serde")]
+pub mod run_length;
+mod svg;
+mod tiled;
 mod unaligned_level;
 
 #[cfg(test)]
 mod test;
 
-pub use self::grid_level::{GridLevel, is_deadend, fill_dead_end_tiles, add_isize_to_usize};
+pub use self::ascii::{CharMap, TileKind};
+pub use self::fov::{field_of_view, line, line_of_sight, visible_cells, FovMode};
+pub use self::grid_level::{
+    add_isize_to_usize, braid_dead_ends, fill_dead_end_tiles, is_deadend, GridLevel,
+};
+pub(crate) use self::grid_level::neighbours;
+#[cfg(feature = "image")]
+pub use self::png::{DefaultPalette, Palette, PngLayout};
+pub use self::path::{distance_map, farthest_tile, find_path};
+pub use self::regions::{
+    connect_regions, flood_fill, is_connected, label_regions, remove_small_regions, Region,
+    RegionId, Regions,
+};
+pub use self::svg::SvgWriter;
+pub use self::tiled::{GidMap, TiledMap};
 pub use self::unaligned_level::{UnalignedLevel, Hitbox, Object};
\ No newline at end of file
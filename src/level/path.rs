@@ -0,0 +1,135 @@
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+use level::{neighbours, GridLevel};
+use util::Neighbourhood;
+
+/// Finds the cheapest path from `start` to `goal` with A*.
+///
+/// Only tiles for which `passable` returns true can be entered and entering a tile costs
+/// `cost` of it, at least 1. Costs add up saturating at `u32::MAX`. Diagonal steps with
+/// `Neighbourhood::Eight` cost the same as orthogonal ones and may pass between two impassable
+/// tiles. Returns the positions along the path, including `start` and `goal`, and its total
+/// cost, or `None` if `goal` can't be reached.
+pub fn find_path<T, P, C>(
+    level: &GridLevel<T>,
+    start: (usize, usize),
+    goal: (usize, usize),
+    neighbourhood: Neighbourhood,
+    passable: P,
+    cost: C,
+) -> Option<(Vec<(usize, usize)>, u32)>
+where
+    P: Fn(&T) -> bool,
+    C: Fn(&T) -> u32,
+{
+    if !level.get_tile_with_tuple(start).is_ok_and(&passable)
+        || !level.get_tile_with_tuple(goal).is_ok_and(&passable)
+    {
+        return None;
+    }
+    let steps = |(x, y): (usize, usize)| {
+        let (dx, dy) = (x.abs_diff(goal.0), y.abs_diff(goal.1));
+        match neighbourhood {
+            Neighbourhood::Four => dx + dy,
+            Neighbourhood::Eight => dx.max(dy),
+        }
+    };
+
+    let (width, height) = (level.get_width(), level.get_height());
+    let mut costs: GridLevel<Option<u32>> = GridLevel::new(width, height);
+    let mut parents = GridLevel::new_filled_with(None, width, height);
+    let mut open = BinaryHeap::new();
+    costs.tiles[start] = Some(0);
+    open.push(Reverse((steps(start) as u32, 0, start)));
+    while let Some(Reverse((_, so_far, pos))) = open.pop() {
+        if pos == goal {
+            let mut path = vec![pos];
+            let mut pos = pos;
+            while let Some(parent) = parents.tiles[pos] {
+                path.push(parent);
+                pos = parent;
+            }
+            path.reverse();
+            return Some((path, so_far));
+        }
+        if costs.tiles[pos].is_some_and(|best| best < so_far) {
+            continue;
+        }
+        for next in neighbours(level, pos, neighbourhood) {
+            let tile = &level.tiles[next];
+            if !passable(tile) {
+                continue;
+            }
+            let total = so_far.saturating_add(cost(tile).max(1));
+            if costs.tiles[next].map_or(true, |best| total < best) {
+                costs.tiles[next] = Some(total);
+                parents.tiles[next] = Some(pos);
+                open.push(Reverse((
+                    total.saturating_add(steps(next) as u32),
+                    total,
+                    next,
+                )));
+            }
+        }
+    }
+    None
+}
+
+/// Computes the cost of the cheapest path from the nearest of `sources` to every tile with
+/// Dijkstra's algorithm. Movement works like in `find_path`. Sources that are outside of the
+/// level or not passable are ignored. Tiles that can't be reached are `None`.
+pub fn distance_map<T, P, C>(
+    level: &GridLevel<T>,
+    sources: &[(usize, usize)],
+    neighbourhood: Neighbourhood,
+    passable: P,
+    cost: C,
+) -> GridLevel<Option<u32>>
+where
+    P: Fn(&T) -> bool,
+    C: Fn(&T) -> u32,
+{
+    let mut distances: GridLevel<Option<u32>> =
+        GridLevel::new(level.get_width(), level.get_height());
+    let mut open = BinaryHeap::new();
+    for &source in sources {
+        if level.get_tile_with_tuple(source).is_ok_and(&passable) {
+            distances.tiles[source] = Some(0);
+            open.push(Reverse((0, source)));
+        }
+    }
+    while let Some(Reverse((so_far, pos))) = open.pop() {
+        if distances.tiles[pos].is_some_and(|best| best < so_far) {
+            continue;
+        }
+        for next in neighbours(level, pos, neighbourhood) {
+            let tile = &level.tiles[next];
+            if !passable(tile) {
+                continue;
+            }
+            let total = so_far.saturating_add(cost(tile).max(1));
+            if distances.tiles[next].map_or(true, |best| total < best) {
+                distances.tiles[next] = Some(total);
+                open.push(Reverse((total, next)));
+            }
+        }
+    }
+    distances
+}
+
+/// The reachable tile with the largest distance in a distance map and its distance. Ties go
+/// to the tile that comes first scanning rows from y = 0.
+pub fn farthest_tile(distances: &GridLevel<Option<u32>>) -> Option<((usize, usize), u32)> {
+    let mut farthest: Option<((usize, usize), u32)> = None;
+    for y in 0..distances.get_height() {
+        for x in 0..distances.get_width() {
+            if let Some(distance) = distances.tiles[(x, y)] {
+                if farthest.map_or(true, |(_, max)| distance > max) {
+                    farthest = Some(((x, y), distance));
+                }
+            }
+        }
+    }
+    farthest
+}
@@ -0,0 +1,264 @@
+use std::fs::File;
+use std::io::{BufReader, Read, Seek, Write};
+use std::path::Path;
+
+use image::{self, DynamicImage, ImageFormat, Rgb, RgbImage};
+
+use level::GridLevel;
+use tile::{Faction, Tile};
+use util::{Error, Grid};
+
+/// Gives tiles of type `T` a color in PNG exports. Closures from `&T` to an RGB color are
+/// palettes too.
+pub trait Palette<T> {
+    fn color(&self, tile: &T) -> [u8; 3];
+}
+
+impl<T, F> Palette<T> for F
+where
+    F: Fn(&T) -> [u8; 3],
+{
+    fn color(&self, tile: &T) -> [u8; 3] {
+        self(tile)
+    }
+}
+
+/// A palette for `Tile` and `Faction` levels. Tiles are colored by their kind, factions get
+/// bright colors that are far apart for consecutive indices.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct DefaultPalette;
+
+const WALL: [u8; 3] = [64, 64, 64];
+const FLOOR: [u8; 3] = [200, 200, 200];
+const VOID: [u8; 3] = [0, 0, 0];
+const DOOR: [u8; 3] = [150, 90, 30];
+const STAIRS_UP: [u8; 3] = [70, 170, 70];
+const STAIRS_DOWN: [u8; 3] = [170, 70, 70];
+
+impl DefaultPalette {
+    /// The inverse of the `Tile` palette, for importing exported levels. Tiles get a payload
+    /// of 0.
+    pub fn tile_for(color: [u8; 3]) -> Option<Tile> {
+        match color {
+            WALL => Some(Tile::Wall(0)),
+            FLOOR => Some(Tile::Floor(0)),
+            VOID => Some(Tile::Void(0)),
+            DOOR => Some(Tile::Door(0)),
+            STAIRS_UP => Some(Tile::StairsUp(0)),
+            STAIRS_DOWN => Some(Tile::StairsDown(0)),
+            _ => None,
+        }
+    }
+}
+
+impl Palette<Tile> for DefaultPalette {
+    fn color(&self, tile: &Tile) -> [u8; 3] {
+        match *tile {
+            Tile::Wall(_) => WALL,
+            Tile::Floor(_) => FLOOR,
+            Tile::Void(_) => VOID,
+            Tile::Door(_) => DOOR,
+            Tile::StairsUp(_) => STAIRS_UP,
+            Tile::StairsDown(_) => STAIRS_DOWN,
+        }
+    }
+}
+
+impl Palette<Faction> for DefaultPalette {
+    fn color(&self, faction: &Faction) -> [u8; 3] {
+        match *faction {
+            Faction::Void => VOID,
+            Faction::Neutral => [128, 128, 128],
+            // Stepping around the hue circle by the golden ratio keeps neighbouring indices
+            // apart.
+            Faction::Faction(index) => hsv((index as f64 * 0.618_033_988_75).fract(), 0.7, 0.95),
+        }
+    }
+}
+
+fn hsv(hue: f64, saturation: f64, value: f64) -> [u8; 3] {
+    let sector = hue * 6.;
+    let f = sector.fract();
+    let (p, q, t) = (
+        value * (1. - saturation),
+        value * (1. - saturation * f),
+        value * (1. - saturation * (1. - f)),
+    );
+    let (r, g, b) = match sector as u32 {
+        0 => (value, t, p),
+        1 => (q, value, p),
+        2 => (p, value, t),
+        3 => (p, q, value),
+        4 => (t, p, value),
+        _ => (value, p, q),
+    };
+    [(r * 255.) as u8, (g * 255.) as u8, (b * 255.) as u8]
+}
+
+/// How a level is laid out in a PNG image, for export as well as import.
+///
+/// Every tile is a `scale` by `scale` square of pixels. With grid lines, a line of pixels is
+/// drawn at every multiple of `scale`, including one extra line along the right and bottom
+/// edges, so an image is `width * scale + 1` pixels wide.
+#[derive(Clone, Debug)]
+pub struct PngLayout {
+    scale: u32,
+    grid: Option<[u8; 3]>,
+}
+
+impl Default for PngLayout {
+    fn default() -> PngLayout {
+        PngLayout::new()
+    }
+}
+
+impl PngLayout {
+    /// One pixel per tile and no grid lines.
+    pub fn new() -> PngLayout {
+        PngLayout {
+            scale: 1,
+            grid: None,
+        }
+    }
+
+    pub fn with_scale(mut self, scale: u32) -> PngLayout {
+        self.scale = scale;
+        self
+    }
+
+    /// Draws grid lines in `color`. Needs a scale of at least 2.
+    pub fn with_grid_lines(mut self, color: [u8; 3]) -> PngLayout {
+        self.grid = Some(color);
+        self
+    }
+
+    fn check(&self) -> Result<(), Error> {
+        match (self.scale, self.grid) {
+            (0, _) => Err(Error::InvalidConfig(
+                "PngLayout: the scale has to be at least 1".to_owned(),
+            )),
+            (1, Some(_)) => Err(Error::InvalidConfig(
+                "PngLayout: grid lines need a scale of at least 2".to_owned(),
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    fn border(&self) -> u32 {
+        if self.grid.is_some() {
+            1
+        } else {
+            0
+        }
+    }
+
+    fn render<T, P>(&self, level: &GridLevel<T>, palette: &P) -> Result<RgbImage, Error>
+    where
+        P: Palette<T> + ?Sized,
+    {
+        self.check()?;
+        let scale = self.scale;
+        let width = level.get_width() as u32 * scale + self.border();
+        let height = level.get_height() as u32 * scale + self.border();
+        let mut image = RgbImage::new(width, height);
+        for py in 0..height {
+            for px in 0..width {
+                let color = match self.grid {
+                    Some(grid) if px % scale == 0 || py % scale == 0 => grid,
+                    _ => {
+                        palette.color(&level.tiles[((px / scale) as usize, (py / scale) as usize)])
+                    }
+                };
+                image.put_pixel(px, py, Rgb(color));
+            }
+        }
+        Ok(image)
+    }
+
+    /// Writes `level` as a PNG file.
+    pub fn save<T, P, Q>(&self, level: &GridLevel<T>, palette: &P, path: Q) -> Result<(), Error>
+    where
+        P: Palette<T> + ?Sized,
+        Q: AsRef<Path>,
+    {
+        let mut file = File::create(path)?;
+        self.write(level, palette, &mut file)
+    }
+
+    /// Writes `level` as PNG data.
+    pub fn write<T, P, W>(
+        &self,
+        level: &GridLevel<T>,
+        palette: &P,
+        writer: &mut W,
+    ) -> Result<(), Error>
+    where
+        P: Palette<T> + ?Sized,
+        W: Write,
+    {
+        let image = DynamicImage::ImageRgb8(self.render(level, palette)?);
+        image.save(writer, ImageFormat::PNG)?;
+        Ok(())
+    }
+
+    /// Reads a level from a PNG file. See `read`.
+    pub fn load<T, F, Q>(&self, path: Q, tile_for: F) -> Result<GridLevel<T>, Error>
+    where
+        F: Fn([u8; 3]) -> Option<T>,
+        Q: AsRef<Path>,
+    {
+        self.read(BufReader::new(File::open(path)?), tile_for)
+    }
+
+    /// Reads a level from PNG data laid out like this layout writes it. Every tile is read
+    /// from the pixel in the middle of its square, and `tile_for` maps that pixel's color to
+    /// a tile. Fails with `Error::Format` for colors without a tile and for images whose size
+    /// doesn't fit the layout.
+    pub fn read<T, F, R>(&self, reader: R, tile_for: F) -> Result<GridLevel<T>, Error>
+    where
+        F: Fn([u8; 3]) -> Option<T>,
+        R: Read + Seek,
+    {
+        self.check()?;
+        let image = image::load(reader, ImageFormat::PNG)?.to_rgb();
+        let scale = self.scale;
+        let (inner_width, inner_height) = (
+            image.width().saturating_sub(self.border()),
+            image.height().saturating_sub(self.border()),
+        );
+        if inner_width == 0
+            || inner_height == 0
+            || inner_width % scale != 0
+            || inner_height % scale != 0
+        {
+            return Err(Error::Format(format!(
+                "a {}x{} image doesn't fit tiles of {} pixels{}",
+                image.width(),
+                image.height(),
+                scale,
+                if self.grid.is_some() {
+                    " with grid lines"
+                } else {
+                    ""
+                }
+            )));
+        }
+        let (width, height) = (inner_width / scale, inner_height / scale);
+        let mut data = Vec::with_capacity((width * height) as usize);
+        for y in 0..height {
+            for x in 0..width {
+                let (px, py) = (x * scale + scale / 2, y * scale + scale / 2);
+                let color = image.get_pixel(px, py).data;
+                data.push(tile_for(color).ok_or_else(|| {
+                    Error::Format(format!(
+                        "the color #{:02x}{:02x}{:02x} of tile ({}, {}) has no tile",
+                        color[0], color[1], color[2], x, y
+                    ))
+                })?);
+            }
+        }
+        Ok(GridLevel {
+            tiles: Grid::from_data(width as usize, data).map_err(Error::Format)?,
+        })
+    }
+}
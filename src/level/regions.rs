@@ -0,0 +1,267 @@
+use std::collections::VecDeque;
+
+use level::{neighbours, GridLevel};
+use tile::Tile;
+use util::Neighbourhood;
+use Point;
+
+/// The label of a tile in the result of `label_regions`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum RegionId {
+    /// The tile isn't passable and belongs to no region.
+    #[default]
+    Impassable,
+    /// The tile belongs to the region with this index.
+    Region(usize),
+}
+
+impl RegionId {
+    /// The index of the region or `None` for impassable tiles.
+    pub fn index(self) -> Option<usize> {
+        match self {
+            RegionId::Impassable => None,
+            RegionId::Region(index) => Some(index),
+        }
+    }
+}
+
+/// A set of passable tiles that are connected to each other.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Region {
+    size: usize,
+    min: Point<usize>,
+    max: Point<usize>,
+}
+
+impl Region {
+    /// The number of tiles in the region.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// The corner of the region's bounding box with the smallest coordinates.
+    pub fn min(&self) -> Point<usize> {
+        self.min
+    }
+
+    /// The corner of the region's bounding box with the largest coordinates.
+    pub fn max(&self) -> Point<usize> {
+        self.max
+    }
+}
+
+/// The connected regions of a level, as found by `label_regions`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Regions {
+    labels: GridLevel<RegionId>,
+    regions: Vec<Region>,
+}
+
+impl Regions {
+    /// The region of every tile of the level.
+    pub fn labels(&self) -> &GridLevel<RegionId> {
+        &self.labels
+    }
+
+    /// The regions in the order they were found, scanning rows from y = 0. A region's index
+    /// here is the index in its `RegionId`.
+    pub fn regions(&self) -> &[Region] {
+        &self.regions
+    }
+
+    pub fn len(&self) -> usize {
+        self.regions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.regions.is_empty()
+    }
+
+    /// The region the tile at `(x, y)` belongs to or `None` for impassable tiles and positions
+    /// outside the level.
+    pub fn region_at(&self, x: usize, y: usize) -> Option<&Region> {
+        let index = self.labels.get_tile(x, y).ok()?.index()?;
+        Some(&self.regions[index])
+    }
+
+    /// Returns true if both tiles are passable and belong to the same region.
+    pub fn connected(&self, a: (usize, usize), b: (usize, usize)) -> bool {
+        match (
+            self.labels.get_tile_with_tuple(a),
+            self.labels.get_tile_with_tuple(b),
+        ) {
+            (Ok(&RegionId::Region(a)), Ok(&RegionId::Region(b))) => a == b,
+            _ => false,
+        }
+    }
+}
+
+/// Returns the positions of all tiles that are connected to `start` through tiles for which
+/// `passable` returns true, in the order they were reached. Empty if `start` itself isn't
+/// passable or is outside of the level.
+pub fn flood_fill<T, F>(
+    level: &GridLevel<T>,
+    start: (usize, usize),
+    neighbourhood: Neighbourhood,
+    passable: F,
+) -> Vec<(usize, usize)>
+where
+    F: Fn(&T) -> bool,
+{
+    let mut seen = GridLevel::new_filled_with(false, level.get_width(), level.get_height());
+    fill(level, &mut seen, start, neighbourhood, &passable)
+}
+
+fn fill<T, F>(
+    level: &GridLevel<T>,
+    seen: &mut GridLevel<bool>,
+    start: (usize, usize),
+    neighbourhood: Neighbourhood,
+    passable: &F,
+) -> Vec<(usize, usize)>
+where
+    F: Fn(&T) -> bool,
+{
+    let mut reached = Vec::new();
+    if !level.get_tile_with_tuple(start).is_ok_and(passable) || seen.tiles[start] {
+        return reached;
+    }
+    seen.tiles[start] = true;
+    let mut queue = VecDeque::new();
+    queue.push_back(start);
+    while let Some(pos) = queue.pop_front() {
+        reached.push(pos);
+        for next in neighbours(level, pos, neighbourhood) {
+            if !seen.tiles[next] && passable(&level.tiles[next]) {
+                seen.tiles[next] = true;
+                queue.push_back(next);
+            }
+        }
+    }
+    reached
+}
+
+/// Splits the tiles for which `passable` returns true into connected regions.
+pub fn label_regions<T, F>(
+    level: &GridLevel<T>,
+    neighbourhood: Neighbourhood,
+    passable: F,
+) -> Regions
+where
+    F: Fn(&T) -> bool,
+{
+    let width = level.get_width();
+    let height = level.get_height();
+    let mut seen = GridLevel::new_filled_with(false, width, height);
+    let mut labels = GridLevel::new(width, height);
+    let mut regions = Vec::new();
+    for y in 0..height {
+        for x in 0..width {
+            let tiles = fill(level, &mut seen, (x, y), neighbourhood, &passable);
+            if tiles.is_empty() {
+                continue;
+            }
+            let mut region = Region {
+                size: tiles.len(),
+                min: Point::new(x, y),
+                max: Point::new(x, y),
+            };
+            for &(tx, ty) in &tiles {
+                labels.tiles[(tx, ty)] = RegionId::Region(regions.len());
+                region.min = Point::new(region.min.x.min(tx), region.min.y.min(ty));
+                region.max = Point::new(region.max.x.max(tx), region.max.y.max(ty));
+            }
+            regions.push(region);
+        }
+    }
+    Regions { labels, regions }
+}
+
+/// Returns true if all passable tiles of `level` are orthogonally connected to each other.
+pub fn is_connected(level: &GridLevel<Tile>) -> bool {
+    label_regions(level, Neighbourhood::Four, Tile::is_passable).len() <= 1
+}
+
+/// Turns every region with fewer than `min_size` tiles into walls. Returns the number of
+/// regions that were removed.
+pub fn remove_small_regions(
+    level: &mut GridLevel<Tile>,
+    min_size: usize,
+    neighbourhood: Neighbourhood,
+) -> usize {
+    let regions = label_regions(level, neighbourhood, Tile::is_passable);
+    let small: Vec<bool> = regions.regions.iter().map(|r| r.size < min_size).collect();
+    for y in 0..level.get_height() {
+        for x in 0..level.get_width() {
+            if let RegionId::Region(index) = regions.labels.tiles[(x, y)] {
+                if small[index] {
+                    level.tiles[(x, y)] = Tile::Wall(0);
+                }
+            }
+        }
+    }
+    small.iter().filter(|&&small| small).count()
+}
+
+/// Digs corridors of floor until all passable tiles are orthogonally connected. Starting with
+/// the first region, the region closest to the connected area is joined to it by the shortest
+/// possible corridor, until none are left. Corridors stay off the outermost tiles of levels
+/// that are at least 3 tiles wide and high.
+///
+/// Returns the number of corridors dug.
+pub fn connect_regions(level: &mut GridLevel<Tile>) -> usize {
+    let width = level.get_width();
+    let height = level.get_height();
+    let inner = |size: usize, pos: usize| size < 3 || (pos > 0 && pos < size - 1);
+
+    let mut corridors = 0;
+    loop {
+        let regions = label_regions(level, Neighbourhood::Four, Tile::is_passable);
+        if regions.len() <= 1 {
+            return corridors;
+        }
+        // Breadth first search from every tile of the first region at once.
+        let mut parents = GridLevel::new_filled_with(None, width, height);
+        let mut queue = VecDeque::new();
+        for y in 0..height {
+            for x in 0..width {
+                if regions.labels.tiles[(x, y)] == RegionId::Region(0) {
+                    parents.tiles[(x, y)] = Some((x, y));
+                    queue.push_back((x, y));
+                }
+            }
+        }
+        let mut end = None;
+        while let Some(pos) = queue.pop_front() {
+            if let RegionId::Region(index) = regions.labels.tiles[pos] {
+                if index != 0 {
+                    end = Some(pos);
+                    break;
+                }
+            }
+            for next in neighbours(level, pos, Neighbourhood::Four) {
+                let diggable = level.tiles[next].is_passable()
+                    || (inner(width, next.0) && inner(height, next.1));
+                if parents.tiles[next].is_none() && diggable {
+                    parents.tiles[next] = Some(pos);
+                    queue.push_back(next);
+                }
+            }
+        }
+
+        let mut pos = match end {
+            Some(end) => end,
+            None => return corridors,
+        };
+        while let Some(parent) = parents.tiles[pos] {
+            if parent == pos {
+                break;
+            }
+            if !level.tiles[pos].is_passable() {
+                level.tiles[pos] = Tile::Floor(0);
+            }
+            pos = parent;
+        }
+        corridors += 1;
+    }
+}
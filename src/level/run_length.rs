@@ -0,0 +1,74 @@
+//! Run-length encoded serialization for `GridLevel`, for use with
+//! `#[serde(with = "dungenon::level::run_length")]`.
+//!
+//! A level is stored as its width and a list of `(count, tile)` runs, row after row. Levels
+//! with large areas of the same tile get a lot smaller than with the default representation.
+//! Levels of more than `MAX_TILES` tiles can neither be serialized nor deserialized.
+
+use std::iter;
+
+use serde::{de, ser, ser::SerializeStruct, Deserialize, Deserializer, Serialize, Serializer};
+
+use level::GridLevel;
+use util::Grid;
+
+/// The largest number of tiles a level may have. Larger levels fail to serialize, and on
+/// deserialization they are rejected before anything is allocated, so a malformed count can't
+/// exhaust memory.
+pub const MAX_TILES: usize = 1 << 24;
+
+pub fn serialize<T, S>(level: &GridLevel<T>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    T: Serialize + PartialEq,
+    S: Serializer,
+{
+    if level.tiles.data.len() > MAX_TILES {
+        return Err(ser::Error::custom(format!(
+            "the level has more than {} tiles",
+            MAX_TILES
+        )));
+    }
+    let mut runs: Vec<(usize, &T)> = Vec::new();
+    for tile in &level.tiles.data {
+        match runs.last_mut() {
+            Some(&mut (ref mut count, last)) if last == tile => *count += 1,
+            _ => runs.push((1, tile)),
+        }
+    }
+    let mut grid = serializer.serialize_struct("RunLengthGrid", 2)?;
+    grid.serialize_field("width", &level.get_width())?;
+    grid.serialize_field("runs", &runs)?;
+    grid.end()
+}
+
+pub fn deserialize<'de, T, D>(deserializer: D) -> Result<GridLevel<T>, D::Error>
+where
+    T: Deserialize<'de> + Clone,
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(rename = "RunLengthGrid")]
+    struct Data<T> {
+        width: usize,
+        runs: Vec<(usize, T)>,
+    }
+    let Data { width, runs } = Data::deserialize(deserializer)?;
+    let mut total: usize = 0;
+    for &(count, _) in &runs {
+        total = match total.checked_add(count) {
+            Some(total) if total <= MAX_TILES => total,
+            _ => {
+                return Err(de::Error::custom(format!(
+                    "the runs add up to more than {} tiles",
+                    MAX_TILES
+                )))
+            }
+        };
+    }
+    let mut data = Vec::with_capacity(total);
+    for (count, tile) in runs {
+        data.extend(iter::repeat(tile).take(count));
+    }
+    let tiles = Grid::from_data(width, data).map_err(de::Error::custom)?;
+    Ok(GridLevel { tiles })
+}
@@ -0,0 +1,187 @@
+use std::f32;
+use std::fmt::Write;
+
+use level::tiled::xml_escape;
+use level::{Hitbox, UnalignedLevel};
+
+/// The most grid lines `SvgWriter` draws in one direction.
+const MAX_GRID_LINES: usize = 1000;
+
+/// Draws an `UnalignedLevel` as an SVG image.
+///
+/// The view box is fitted to the objects plus a margin. Every object is drawn as a `circle`
+/// or a `rect` whose `style` attribute comes from the style callback, for example
+/// `fill:green;stroke:black;stroke-width:0.05`. Objects whose hitbox isn't finite are left
+/// out.
+#[derive(Clone, Debug)]
+pub struct SvgWriter {
+    margin: f32,
+    scale: f32,
+    grid: Option<f32>,
+}
+
+impl Default for SvgWriter {
+    fn default() -> SvgWriter {
+        SvgWriter::new()
+    }
+}
+
+impl SvgWriter {
+    /// A margin of 1 unit, 10 pixels per unit and no grid.
+    pub fn new() -> SvgWriter {
+        SvgWriter {
+            margin: 1.,
+            scale: 10.,
+            grid: None,
+        }
+    }
+
+    /// Sets the space around the objects, in level units.
+    pub fn with_margin(mut self, margin: f32) -> SvgWriter {
+        self.margin = margin;
+        self
+    }
+
+    /// Sets the size of the image in pixels per level unit.
+    pub fn with_scale(mut self, scale: f32) -> SvgWriter {
+        self.scale = scale;
+        self
+    }
+
+    /// Draws grid lines every `spacing` units behind the objects. The grid is left out if it
+    /// would need more than 1000 lines in either direction.
+    pub fn with_grid(mut self, spacing: f32) -> SvgWriter {
+        self.grid = Some(spacing);
+        self
+    }
+
+    /// The smallest and largest corner of the view box.
+    fn bounds<T>(&self, level: &UnalignedLevel<T>) -> ((f32, f32), (f32, f32)) {
+        let mut min = (f32::INFINITY, f32::INFINITY);
+        let mut max = (f32::NEG_INFINITY, f32::NEG_INFINITY);
+        for (low, high) in level.objects().iter().filter_map(|o| extent(&o.hitbox)) {
+            min = (min.0.min(low.0), min.1.min(low.1));
+            max = (max.0.max(high.0), max.1.max(high.1));
+        }
+        if min.0 > max.0 {
+            min = (0., 0.);
+            max = (0., 0.);
+        }
+        (
+            (min.0 - self.margin, min.1 - self.margin),
+            (max.0 + self.margin, max.1 + self.margin),
+        )
+    }
+
+    pub fn to_svg<T, F>(&self, level: &UnalignedLevel<T>, style: F) -> String
+    where
+        F: Fn(&T) -> String,
+    {
+        let (min, max) = self.bounds(level);
+        let (width, height) = (max.0 - min.0, max.1 - min.1);
+        let mut svg = String::new();
+        // Writing to a `String` can't fail.
+        let _ = writeln!(
+            svg,
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" \
+             viewBox=\"{} {} {} {}\">",
+            width * self.scale,
+            height * self.scale,
+            min.0,
+            min.1,
+            width,
+            height
+        );
+        if let Some(spacing) = self.grid {
+            if let (Some(xs), Some(ys)) = (
+                grid_lines(min.0, max.0, spacing),
+                grid_lines(min.1, max.1, spacing),
+            ) {
+                let _ = writeln!(
+                    svg,
+                    " <g stroke=\"#ccc\" stroke-width=\"{}\">",
+                    spacing / 20.
+                );
+                for x in xs {
+                    let _ = writeln!(
+                        svg,
+                        "  <line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\"/>",
+                        x, min.1, x, max.1
+                    );
+                }
+                for y in ys {
+                    let _ = writeln!(
+                        svg,
+                        "  <line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\"/>",
+                        min.0, y, max.0, y
+                    );
+                }
+                svg.push_str(" </g>\n");
+            }
+        }
+        for object in level.objects() {
+            if extent(&object.hitbox).is_none() {
+                continue;
+            }
+            let style = xml_escape(&style(&object.value));
+            let _ = match object.hitbox {
+                Hitbox::Circle(center, radius) => writeln!(
+                    svg,
+                    " <circle cx=\"{}\" cy=\"{}\" r=\"{}\" style=\"{}\"/>",
+                    center.x,
+                    center.y,
+                    radius.abs(),
+                    style
+                ),
+                Hitbox::Aabb(center, sides) => writeln!(
+                    svg,
+                    " <rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" style=\"{}\"/>",
+                    center.x - sides.x.abs() / 2.,
+                    center.y - sides.y.abs() / 2.,
+                    sides.x.abs(),
+                    sides.y.abs(),
+                    style
+                ),
+            };
+        }
+        svg.push_str("</svg>\n");
+        svg
+    }
+}
+
+/// The smallest and largest corner of a hitbox, or `None` if they aren't finite.
+fn extent(hitbox: &Hitbox) -> Option<((f32, f32), (f32, f32))> {
+    let (center, half) = match *hitbox {
+        Hitbox::Circle(center, radius) => (center, (radius.abs(), radius.abs())),
+        Hitbox::Aabb(center, sides) => (center, (sides.x.abs() / 2., sides.y.abs() / 2.)),
+    };
+    let (low, high) = (
+        (center.x - half.0, center.y - half.1),
+        (center.x + half.0, center.y + half.1),
+    );
+    if [low.0, low.1, high.0, high.1].iter().all(|v| v.is_finite()) {
+        Some((low, high))
+    } else {
+        None
+    }
+}
+
+/// The positions of the grid lines between `min` and `max`, or `None` if there would be too
+/// many of them.
+fn grid_lines(min: f32, max: f32, spacing: f32) -> Option<Vec<f32>> {
+    if spacing.is_nan() || spacing <= 0. {
+        return None;
+    }
+    let spacing = f64::from(spacing);
+    let first = (f64::from(min) / spacing).ceil();
+    let last = (f64::from(max) / spacing).floor();
+    let count = last - first + 1.;
+    if !count.is_finite() || count > MAX_GRID_LINES as f64 {
+        return None;
+    }
+    Some(
+        (0..count.max(0.) as usize)
+            .map(|i| ((first + i as f64) * spacing) as f32)
+            .collect(),
+    )
+}
@@ -0,0 +1,378 @@
+use std::convert::TryFrom;
+use std::fmt::Write;
+
+use level::ascii::{payload, same_kind};
+use level::{GridLevel, Hitbox, TileKind, UnalignedLevel};
+use tile::Tile;
+use util::Error;
+
+/// Maps tiles to global tile ids (GIDs) of a Tiled tileset. A tile of a mapped kind gets the
+/// kind's first GID plus its payload, every other tile gets 0, which Tiled draws as empty.
+#[derive(Clone, Default)]
+pub struct GidMap {
+    kinds: Vec<(TileKind, u32)>,
+}
+
+impl GidMap {
+    pub fn new() -> GidMap {
+        GidMap { kinds: Vec::new() }
+    }
+
+    /// Maps tiles of a kind, like `Tile::Wall`, to `first_gid + payload`.
+    pub fn with_kind(mut self, kind: TileKind, first_gid: u32) -> GidMap {
+        self.kinds.retain(|&(k, _)| !same_kind(&k(0), &kind(0)));
+        self.kinds.push((kind, first_gid));
+        self
+    }
+
+    /// The GID of `tile`, or 0 if its kind isn't mapped or its GID doesn't fit into a `u32`.
+    pub fn gid(&self, tile: &Tile) -> u32 {
+        self.kinds
+            .iter()
+            .find(|&&(kind, _)| same_kind(&kind(0), tile))
+            .and_then(|&(_, first)| {
+                u32::try_from(payload(tile))
+                    .ok()
+                    .and_then(|payload| first.checked_add(payload))
+            })
+            .unwrap_or(0)
+    }
+}
+
+enum Layer {
+    Tiles(String, Vec<u32>),
+    Objects(String, Vec<MapObject>),
+}
+
+struct MapObject {
+    kind: String,
+    x: f32,
+    y: f32,
+    width: f32,
+    height: f32,
+    ellipse: bool,
+}
+
+/// An orthogonal map for the Tiled map editor, written as Tiled JSON or TMX.
+///
+/// Layers are drawn in the order they are added. Positions of `UnalignedLevel` objects are in
+/// tiles and get scaled by the tile size.
+pub struct TiledMap {
+    width: usize,
+    height: usize,
+    tile_width: u32,
+    tile_height: u32,
+    tilesets: Vec<(u32, String)>,
+    layers: Vec<Layer>,
+}
+
+impl TiledMap {
+    /// Creates an empty map with 16x16 pixel tiles.
+    pub fn new(width: usize, height: usize) -> TiledMap {
+        TiledMap {
+            width,
+            height,
+            tile_width: 16,
+            tile_height: 16,
+            tilesets: Vec::new(),
+            layers: Vec::new(),
+        }
+    }
+
+    pub fn with_tile_size(mut self, width: u32, height: u32) -> TiledMap {
+        self.tile_width = width;
+        self.tile_height = height;
+        self
+    }
+
+    /// References an external tileset file, like `dungeon.tsx`, whose tiles start at
+    /// `first_gid`.
+    pub fn with_tileset(mut self, first_gid: u32, source: &str) -> TiledMap {
+        self.tilesets.push((first_gid, source.to_owned()));
+        self
+    }
+
+    /// Adds a tile layer with the GID of every tile of `level`. Fails if `level` isn't as
+    /// large as the map.
+    pub fn add_tile_layer<T, F>(
+        &mut self,
+        name: &str,
+        level: &GridLevel<T>,
+        gid: F,
+    ) -> Result<(), Error>
+    where
+        F: Fn(&T) -> u32,
+    {
+        if (level.get_width(), level.get_height()) != (self.width, self.height) {
+            return Err(Error::SizeMismatch {
+                expected: (self.width, self.height),
+                found: (level.get_width(), level.get_height()),
+            });
+        }
+        let gids = level.tiles.data.iter().map(gid).collect();
+        self.layers.push(Layer::Tiles(name.to_owned(), gids));
+        Ok(())
+    }
+
+    /// Adds an object layer with a rectangle or ellipse for every object of `level`.
+    /// `object_type` gives the type of each object. Fails if a hitbox isn't finite.
+    pub fn add_object_layer<T, F>(
+        &mut self,
+        name: &str,
+        level: &UnalignedLevel<T>,
+        object_type: F,
+    ) -> Result<(), Error>
+    where
+        F: Fn(&T) -> String,
+    {
+        let (tw, th) = (self.tile_width as f32, self.tile_height as f32);
+        let objects: Vec<MapObject> = level
+            .objects()
+            .iter()
+            .map(|object| {
+                let (center, half, ellipse) = match object.hitbox {
+                    Hitbox::Circle(center, radius) => {
+                        ((center.x, center.y), (radius, radius), true)
+                    }
+                    Hitbox::Aabb(center, sides) => (
+                        (center.x, center.y),
+                        (sides.x.abs() / 2., sides.y.abs() / 2.),
+                        false,
+                    ),
+                };
+                MapObject {
+                    kind: object_type(&object.value),
+                    x: (center.0 - half.0) * tw,
+                    y: (center.1 - half.1) * th,
+                    width: half.0 * 2. * tw,
+                    height: half.1 * 2. * th,
+                    ellipse,
+                }
+            })
+            .collect();
+        if let Some(i) = objects.iter().position(|object| {
+            ![object.x, object.y, object.width, object.height]
+                .iter()
+                .all(|v| v.is_finite())
+        }) {
+            return Err(Error::Format(format!(
+                "object {} of layer \"{}\" has a hitbox that isn't finite",
+                i, name
+            )));
+        }
+        self.layers.push(Layer::Objects(name.to_owned(), objects));
+        Ok(())
+    }
+
+    fn object_count(&self) -> usize {
+        self.layers
+            .iter()
+            .map(|layer| match *layer {
+                Layer::Objects(_, ref objects) => objects.len(),
+                Layer::Tiles(..) => 0,
+            })
+            .sum()
+    }
+
+    /// The map in Tiled's JSON format.
+    pub fn to_json(&self) -> String {
+        let mut json = String::new();
+        // Writing to a `String` can't fail.
+        let _ = write!(
+            json,
+            "{{\"type\":\"map\",\"version\":\"1.10\",\"orientation\":\"orthogonal\",\
+             \"renderorder\":\"right-down\",\"infinite\":false,\"width\":{},\"height\":{},\
+             \"tilewidth\":{},\"tileheight\":{},\"nextlayerid\":{},\"nextobjectid\":{},\
+             \"tilesets\":[",
+            self.width,
+            self.height,
+            self.tile_width,
+            self.tile_height,
+            self.layers.len() + 1,
+            self.object_count() + 1
+        );
+        for (i, &(first_gid, ref source)) in self.tilesets.iter().enumerate() {
+            if i > 0 {
+                json.push(',');
+            }
+            let _ = write!(
+                json,
+                "{{\"firstgid\":{},\"source\":\"{}\"}}",
+                first_gid,
+                json_escape(source)
+            );
+        }
+        json.push_str("],\"layers\":[");
+        let mut object_id = 1;
+        for (i, layer) in self.layers.iter().enumerate() {
+            if i > 0 {
+                json.push(',');
+            }
+            match *layer {
+                Layer::Tiles(ref name, ref gids) => {
+                    let _ = write!(
+                        json,
+                        "{{\"id\":{},\"name\":\"{}\",\"type\":\"tilelayer\",\"x\":0,\"y\":0,\
+                         \"width\":{},\"height\":{},\"opacity\":1,\"visible\":true,\"data\":[",
+                        i + 1,
+                        json_escape(name),
+                        self.width,
+                        self.height
+                    );
+                    for (j, gid) in gids.iter().enumerate() {
+                        if j > 0 {
+                            json.push(',');
+                        }
+                        let _ = write!(json, "{}", gid);
+                    }
+                    json.push_str("]}");
+                }
+                Layer::Objects(ref name, ref objects) => {
+                    let _ = write!(
+                        json,
+                        "{{\"id\":{},\"name\":\"{}\",\"type\":\"objectgroup\",\"x\":0,\"y\":0,\
+                         \"opacity\":1,\"visible\":true,\"draworder\":\"topdown\",\"objects\":[",
+                        i + 1,
+                        json_escape(name)
+                    );
+                    for (j, object) in objects.iter().enumerate() {
+                        if j > 0 {
+                            json.push(',');
+                        }
+                        let _ = write!(
+                            json,
+                            "{{\"id\":{},\"name\":\"\",\"type\":\"{}\",\"x\":{},\"y\":{},\
+                             \"width\":{},\"height\":{},\"rotation\":0,\"visible\":true{}}}",
+                            object_id,
+                            json_escape(&object.kind),
+                            object.x,
+                            object.y,
+                            object.width,
+                            object.height,
+                            if object.ellipse {
+                                ",\"ellipse\":true"
+                            } else {
+                                ""
+                            }
+                        );
+                        object_id += 1;
+                    }
+                    json.push_str("]}");
+                }
+            }
+        }
+        json.push_str("]}");
+        json
+    }
+
+    /// The map in Tiled's XML based TMX format, with CSV encoded tile layers.
+    pub fn to_tmx(&self) -> String {
+        let mut tmx = String::new();
+        let _ = writeln!(tmx, "<?xml version=\"1.0\" encoding=\"UTF-8\"?>");
+        let _ = writeln!(
+            tmx,
+            "<map version=\"1.10\" orientation=\"orthogonal\" renderorder=\"right-down\" \
+             width=\"{}\" height=\"{}\" tilewidth=\"{}\" tileheight=\"{}\" infinite=\"0\" \
+             nextlayerid=\"{}\" nextobjectid=\"{}\">",
+            self.width,
+            self.height,
+            self.tile_width,
+            self.tile_height,
+            self.layers.len() + 1,
+            self.object_count() + 1
+        );
+        for &(first_gid, ref source) in &self.tilesets {
+            let _ = writeln!(
+                tmx,
+                " <tileset firstgid=\"{}\" source=\"{}\"/>",
+                first_gid,
+                xml_escape(source)
+            );
+        }
+        let mut object_id = 1;
+        for (i, layer) in self.layers.iter().enumerate() {
+            match *layer {
+                Layer::Tiles(ref name, ref gids) => {
+                    let _ = writeln!(
+                        tmx,
+                        " <layer id=\"{}\" name=\"{}\" width=\"{}\" height=\"{}\">",
+                        i + 1,
+                        xml_escape(name),
+                        self.width,
+                        self.height
+                    );
+                    tmx.push_str("  <data encoding=\"csv\">\n");
+                    for (j, row) in gids.chunks(self.width.max(1)).enumerate() {
+                        let row: Vec<String> = row.iter().map(u32::to_string).collect();
+                        tmx.push_str(&row.join(","));
+                        if j + 1 < self.height {
+                            tmx.push(',');
+                        }
+                        tmx.push('\n');
+                    }
+                    tmx.push_str("  </data>\n </layer>\n");
+                }
+                Layer::Objects(ref name, ref objects) => {
+                    let _ = writeln!(
+                        tmx,
+                        " <objectgroup id=\"{}\" name=\"{}\">",
+                        i + 1,
+                        xml_escape(name)
+                    );
+                    for object in objects {
+                        let _ = write!(
+                            tmx,
+                            "  <object id=\"{}\" type=\"{}\" x=\"{}\" y=\"{}\" width=\"{}\" \
+                             height=\"{}\"",
+                            object_id,
+                            xml_escape(&object.kind),
+                            object.x,
+                            object.y,
+                            object.width,
+                            object.height
+                        );
+                        if object.ellipse {
+                            tmx.push_str(">\n   <ellipse/>\n  </object>\n");
+                        } else {
+                            tmx.push_str("/>\n");
+                        }
+                        object_id += 1;
+                    }
+                    tmx.push_str(" </objectgroup>\n");
+                }
+            }
+        }
+        tmx.push_str("</map>\n");
+        tmx
+    }
+}
+
+fn json_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(escaped, "\\u{:04x}", c as u32);
+            }
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+pub(crate) fn xml_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
@@ -5,6 +5,10 @@ use na::zero;
 use rayon::iter::ParallelIterator;
 use rayon::prelude::*;
 
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct UnalignedLevel<T> {
     objects: Vec<Object<T>>,
 }
@@ -16,6 +20,10 @@ impl<T> UnalignedLevel<T> {
         }
     }
 
+    pub fn objects(&self) -> &[Object<T>] {
+        &self.objects
+    }
+
     /// Adds an `Object` to the level without checking for collision with other objects
     pub fn add_unchecked(&mut self, obj: Object<T>) {
         self.objects.push(obj);
@@ -38,6 +46,7 @@ impl<T: Sync> UnalignedLevel<T> {
     }
 }
 
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Object<T> {
     pub value: T,
     pub hitbox: Hitbox,
@@ -52,10 +61,38 @@ impl<T> Object<T> {
     }
 }
 
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Hitbox {
-    Circle(Vector<f32>, f32),
+    Circle(
+        #[cfg_attr(feature = "serde", serde(with = "vector"))] Vector<f32>,
+        f32,
+    ),
     ///First vector denotes the center of the AABB and the second vector denotes the dimensions(width, height) of the AABB
-    Aabb(Vector<f32>, Vector<f32>),
+    Aabb(
+        #[cfg_attr(feature = "serde", serde(with = "vector"))] Vector<f32>,
+        #[cfg_attr(feature = "serde", serde(with = "vector"))] Vector<f32>,
+    ),
+}
+
+/// Vectors are serialized as `(x, y)` tuples.
+#[cfg(feature = "serde")]
+mod vector {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+    use Vector;
+
+    pub fn serialize<S: Serializer>(
+        vector: &Vector<f32>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        (vector.x, vector.y).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vector<f32>, D::Error> {
+        let (x, y) = <(f32, f32)>::deserialize(deserializer)?;
+        Ok(Vector::new(x, y))
+    }
 }
 impl Hitbox {
     pub fn collides(&self, hitbox: &Hitbox) -> bool {
@@ -1,7 +1,12 @@
+#[cfg(feature = "image")]
+extern crate image;
 extern crate nalgebra as na;
 extern crate poisson;
 extern crate rand;
+extern crate rand_xorshift;
 extern crate rayon;
+#[cfg(feature = "serde")]
+extern crate serde;
 extern crate smallvec;
 
 pub mod generator;
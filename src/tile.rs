@@ -1,15 +1,34 @@
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 use std::default::Default;
 
 #[derive(Clone, Debug, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 //the indexes inside the enums are for flexibility. You can create an extra array for different
 //types of particular tile type and index it with the index. For example you could have an array of
 //different wall texture variations and have your level generator algorithm put random indexes in
 //the walls so that the level would be more visually varied. You could also also have an array of
 //different types of floors which each have their own properties (normal floor, icy floor, lava floor etc.)
+//`generator::Autotiler` can fill them with wall and floor shape indices for a renderer.
 pub enum Tile {
     Wall(usize),
     Floor(usize),
     Void(usize),
+    Door(usize),
+    /// Stairs leading up, for example the entrance of a level.
+    StairsUp(usize),
+    /// Stairs leading down, for example the exit of a level.
+    StairsDown(usize),
+}
+
+impl Tile {
+    /// Returns true for tiles that can be walked through.
+    pub fn is_passable(&self) -> bool {
+        match *self {
+            Tile::Floor(_) | Tile::Door(_) | Tile::StairsUp(_) | Tile::StairsDown(_) => true,
+            Tile::Wall(_) | Tile::Void(_) => false,
+        }
+    }
 }
 
 impl Default for Tile {
@@ -19,6 +38,7 @@ impl Default for Tile {
 }
 
 #[derive(Clone, Debug, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Faction {
 	Faction(usize),
 	Neutral,
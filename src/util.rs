@@ -1,8 +1,15 @@
+use level::add_isize_to_usize;
+use rand::Rng;
+#[cfg(feature = "serde")]
+use serde::{de, ser::SerializeStruct, Deserialize, Deserializer, Serialize, Serializer};
 use std::default::Default;
+use std::error;
+use std::fmt;
+use std::io;
 use std::ops::{Index, IndexMut};
 use Vector;
 
-#[derive(Clone)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct Grid<T> {
     pub(crate) data: Vec<T>,
     width: usize,
@@ -36,6 +43,49 @@ impl<T: Clone> Grid<T> {
     }
 }
 
+impl<T> Grid<T> {
+    /// Builds a grid from its rows, concatenated. Fails if `width` is 0 or `data` can't be
+    /// split into rows of `width` tiles.
+    pub(crate) fn from_data(width: usize, data: Vec<T>) -> Result<Grid<T>, String> {
+        if width == 0 {
+            return Err("a grid can't be 0 tiles wide".to_owned());
+        }
+        if data.len() % width != 0 {
+            return Err(format!(
+                "{} tiles can't be split into rows of {}",
+                data.len(),
+                width
+            ));
+        }
+        Ok(Grid { data, width })
+    }
+}
+
+/// Grids are serialized as their width and their tiles, row after row.
+#[cfg(feature = "serde")]
+impl<T: Serialize> Serialize for Grid<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut grid = serializer.serialize_struct("Grid", 2)?;
+        grid.serialize_field("width", &self.width)?;
+        grid.serialize_field("data", &self.data)?;
+        grid.end()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for Grid<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Grid<T>, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(rename = "Grid")]
+        struct Data<T> {
+            width: usize,
+            data: Vec<T>,
+        }
+        let Data { width, data } = Data::deserialize(deserializer)?;
+        Grid::from_data(width, data).map_err(de::Error::custom)
+    }
+}
+
 impl<T> Index<(usize, usize)> for Grid<T> {
     type Output = T;
 
@@ -98,6 +148,16 @@ impl Direction {
         }
     }
 
+    /// Returns the position one step away from `(x, y)` in this direction or `None` if that
+    /// position would have a negative coordinate.
+    pub fn offset(&self, (x, y): (usize, usize)) -> Option<(usize, usize)> {
+        let (dx, dy) = self.get_tuple();
+        match (add_isize_to_usize(dx, x), add_isize_to_usize(dy, y)) {
+            (Some(x), Some(y)) => Some((x, y)),
+            _ => None,
+        }
+    }
+
     pub fn get_orthogonal_dirs() -> &'static [Direction] {
         &ORTHOGONAL
     }
@@ -106,6 +166,126 @@ impl Direction {
     }
 }
 
+/// Which tiles count as neighbours of a tile.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Neighbourhood {
+    /// The four orthogonally adjacent tiles.
+    Four,
+    /// The four orthogonally and the four diagonally adjacent tiles.
+    Eight,
+}
+
+impl Neighbourhood {
+    pub fn dirs(self) -> &'static [Direction] {
+        match self {
+            Neighbourhood::Four => Direction::get_orthogonal_dirs(),
+            Neighbourhood::Eight => Direction::get_dirs(),
+        }
+    }
+}
+
+/// A random index below `len`. Seeded generators sample `u64` instead of `usize`, so that
+/// the same seed gives the same level on 32 and 64 bit targets.
+pub(crate) fn random_index<R: Rng + ?Sized>(rng: &mut R, len: usize) -> usize {
+    rng.gen_range(0, len as u64) as usize
+}
+
+/// Disjoint sets of the numbers below `size`.
+pub(crate) struct UnionFind {
+    parents: Vec<usize>,
+}
+
+impl UnionFind {
+    pub(crate) fn new(size: usize) -> UnionFind {
+        UnionFind {
+            parents: (0..size).collect(),
+        }
+    }
+
+    pub(crate) fn find(&mut self, mut i: usize) -> usize {
+        while self.parents[i] != i {
+            self.parents[i] = self.parents[self.parents[i]];
+            i = self.parents[i];
+        }
+        i
+    }
+
+    /// Joins the sets of `a` and `b`. Returns false if they already were in the same set.
+    pub(crate) fn union(&mut self, a: usize, b: usize) -> bool {
+        let a = self.find(a);
+        let b = self.find(b);
+        if a == b {
+            return false;
+        }
+        self.parents[a] = b;
+        true
+    }
+}
+
+#[derive(Debug)]
 pub enum Error {
-    IndexOutOfBounds,
+    /// `pos` is outside of a level that is `size` (width, height) tiles large.
+    IndexOutOfBounds {
+        pos: (usize, usize),
+        size: (usize, usize),
+    },
+    /// A generator was given parameters it can't work with. The message says which and why.
+    InvalidConfig(String),
+    /// Two levels that have to be equally large aren't. Sizes are (width, height).
+    SizeMismatch {
+        expected: (usize, usize),
+        found: (usize, usize),
+    },
+    /// A generator gave up without producing a level. The message says why.
+    GenerationFailed(String),
+    /// Data that was read into a level is malformed.
+    Format(String),
+    Io(io::Error),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use self::Error::*;
+        match *self {
+            IndexOutOfBounds { pos, size } => write!(
+                f,
+                "position ({}, {}) is outside of the {}x{} level",
+                pos.0, pos.1, size.0, size.1
+            ),
+            InvalidConfig(ref message) => write!(f, "invalid configuration: {}", message),
+            SizeMismatch { expected, found } => write!(
+                f,
+                "expected a {}x{} level but got a {}x{} one",
+                expected.0, expected.1, found.0, found.1
+            ),
+            GenerationFailed(ref message) => write!(f, "generation failed: {}", message),
+            Format(ref message) => write!(f, "malformed level data: {}", message),
+            Io(ref error) => write!(f, "I/O error: {}", error),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match *self {
+            Error::Io(ref error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(error: io::Error) -> Error {
+        Error::Io(error)
+    }
+}
+
+#[cfg(feature = "image")]
+impl From<::image::ImageError> for Error {
+    fn from(error: ::image::ImageError) -> Error {
+        match error {
+            ::image::ImageError::IoError(error) => Error::Io(error),
+            error => Error::Format(error.to_string()),
+        }
+    }
 }
@@ -0,0 +1,66 @@
+extern crate dungenon;
+
+use dungenon::{
+    generator::CaveGen,
+    level::{CharMap, GridLevel},
+    tile::Tile,
+    util::Error,
+};
+
+#[test]
+fn levels_round_trip() {
+    let mut level = GridLevel::new_filled_with(Tile::Wall(0), 30, 20);
+    CaveGen::with_seed(3).generate(&mut level);
+    *level.get_mut_tile(0, 0).unwrap() = Tile::Void(0);
+    *level.get_mut_tile(1, 0).unwrap() = Tile::Door(0);
+    *level.get_mut_tile(2, 0).unwrap() = Tile::StairsDown(0);
+
+    let text = level.to_ascii(&CharMap::default());
+    assert_eq!(text.lines().count(), 20);
+    assert!(text.starts_with(" +>"));
+    assert_eq!(format!("{}", level), text);
+    assert_eq!(
+        GridLevel::from_ascii(&text, &CharMap::default()).unwrap(),
+        level
+    );
+}
+
+#[test]
+fn custom_maps() {
+    let map = CharMap::default()
+        .with_digits(Tile::Wall)
+        .with_tile(Tile::Floor(1), '~');
+    let level = GridLevel::from_ascii("#19\n.~ \n", &map).unwrap();
+    assert_eq!(level.get_tile(0, 0).ok(), Some(&Tile::Wall(0)));
+    assert_eq!(level.get_tile(2, 0).ok(), Some(&Tile::Wall(9)));
+    assert_eq!(level.get_tile(1, 1).ok(), Some(&Tile::Floor(1)));
+    assert_eq!(level.get_tile(2, 1).ok(), Some(&Tile::Void(0)));
+    assert_eq!(level.to_ascii(&map), "#19\n.~ \n");
+
+    let mut level = level;
+    *level.get_mut_tile(0, 1).unwrap() = Tile::Wall(12);
+    *level.get_mut_tile(1, 1).unwrap() = Tile::Door(3);
+    assert_eq!(level.to_ascii(&map), "#19\n#+ \n");
+    let walls = CharMap::new().with_kind(Tile::Wall, 'W').with_unknown('*');
+    assert_eq!(level.to_ascii(&walls), "WWW\nW**\n");
+    let digits = CharMap::new().with_digits(Tile::Wall).with_unknown('*');
+    assert_eq!(level.to_ascii(&digits), "019\n***\n");
+}
+
+#[test]
+fn malformed_text_is_rejected() {
+    let map = CharMap::default();
+    match GridLevel::from_ascii("###\n#.\n###", &map) {
+        Err(Error::Format(message)) => assert!(message.contains("line 2"), "{}", message),
+        other => panic!("expected an error, got {:?}", other),
+    }
+    match GridLevel::from_ascii("###\n#x#\n###", &map) {
+        Err(Error::Format(message)) => {
+            assert!(message.contains("'x'"), "{}", message);
+            assert!(message.contains("line 2, column 2"), "{}", message);
+        }
+        other => panic!("expected an error, got {:?}", other),
+    }
+    assert!(GridLevel::from_ascii("", &map).is_err());
+    assert!(GridLevel::from_ascii("\n\n", &map).is_err());
+}
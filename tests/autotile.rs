@@ -0,0 +1,115 @@
+extern crate dungenon;
+
+mod common;
+
+use std::collections::HashSet;
+
+use dungenon::{
+    generator::{autotile_index, AutotileMask, Autotiler, CaveGen},
+    level::GridLevel,
+    tile::Tile,
+    util::Error,
+};
+
+use common::level_from;
+
+#[test]
+fn masks_follow_the_neighbours() {
+    let level = level_from(&[
+        "#####", //
+        "#...#", //
+        "#.#.#", //
+        "#...+", //
+        "#### ", //
+    ]);
+    // The lone pillar in the middle connects to nothing.
+    assert_eq!(autotile_index(&level, 2, 2, AutotileMask::Four), Some(0));
+    assert_eq!(autotile_index(&level, 2, 2, AutotileMask::Eight), Some(0));
+    assert_eq!(autotile_index(&level, 2, 2, AutotileMask::Blob), Some(0));
+    // The top wall connects to the left, to the right and to the edge above it.
+    assert_eq!(
+        autotile_index(&level, 2, 0, AutotileMask::Four),
+        Some(1 | 2 | 8)
+    );
+    assert_eq!(
+        autotile_index(&level, 2, 0, AutotileMask::Eight),
+        Some(1 | 2 | 4 | 64 | 128)
+    );
+    // Walls connect to doors.
+    assert_eq!(
+        autotile_index(&level, 4, 2, AutotileMask::Four),
+        Some(1 | 2 | 4)
+    );
+    // Floors connect to passable tiles.
+    assert_eq!(
+        autotile_index(&level, 1, 1, AutotileMask::Four),
+        Some(2 | 4)
+    );
+    assert_eq!(
+        autotile_index(&level, 3, 3, AutotileMask::Four),
+        Some(1 | 2 | 8)
+    );
+    assert_eq!(autotile_index(&level, 4, 4, AutotileMask::Four), None);
+    assert_eq!(autotile_index(&level, 5, 0, AutotileMask::Four), None);
+    // Surrounded on all sides.
+    let solid = GridLevel::new_filled_with(Tile::Wall(0), 3, 3);
+    assert_eq!(autotile_index(&solid, 1, 1, AutotileMask::Four), Some(15));
+    assert_eq!(autotile_index(&solid, 1, 1, AutotileMask::Eight), Some(255));
+    assert_eq!(autotile_index(&solid, 1, 1, AutotileMask::Blob), Some(46));
+}
+
+#[test]
+fn blob_masks_have_47_indices() {
+    let mut level = GridLevel::new_filled_with(Tile::Wall(0), 80, 60);
+    CaveGen::with_seed(2)
+        .with_fill_ratio(0.5)
+        .generate(&mut level);
+    let mut seen = HashSet::new();
+    for y in 0..60 {
+        for x in 0..80 {
+            let index = autotile_index(&level, x, y, AutotileMask::Blob).unwrap();
+            assert!(index < 47);
+            seen.insert(index);
+        }
+    }
+    assert!(seen.len() > 20);
+}
+
+#[test]
+fn payloads_come_from_the_tables() {
+    let mut level = level_from(&[
+        "###", //
+        "#..", //
+        "#..", //
+    ]);
+    let walls: Vec<usize> = (0..16).map(|i| 100 + i).collect();
+    let floors: Vec<usize> = (0..16).map(|i| 200 + i).collect();
+    Autotiler::new(AutotileMask::Four)
+        .with_wall_table(walls)
+        .with_floor_table(floors)
+        .autotile(&mut level)
+        .unwrap();
+    assert_eq!(level.get_tile(0, 0).ok(), Some(&Tile::Wall(115)));
+    assert_eq!(
+        level.get_tile(1, 0).ok(),
+        Some(&Tile::Wall(100 + 1 + 2 + 8))
+    );
+    assert_eq!(level.get_tile(1, 1).ok(), Some(&Tile::Floor(200 + 2 + 4)));
+    assert_eq!(level.get_tile(2, 2).ok(), Some(&Tile::Floor(215)));
+
+    let before = level.clone();
+    match Autotiler::new(AutotileMask::Blob)
+        .with_wall_table(vec![0; 16])
+        .autotile(&mut level)
+    {
+        Err(Error::InvalidConfig(_)) => {}
+        other => panic!("expected an error, got {:?}", other),
+    }
+    assert_eq!(level, before);
+
+    Autotiler::new(AutotileMask::Four)
+        .without_walls()
+        .autotile(&mut level)
+        .unwrap();
+    assert_eq!(level, before);
+}
@@ -0,0 +1,77 @@
+extern crate dungenon;
+extern crate rand;
+extern crate rand_xorshift;
+
+use dungenon::{
+    generator::{MazeAlgorithm, MazeGen},
+    level::{braid_dead_ends, is_deadend, GridLevel},
+    tile::Tile,
+};
+
+use rand::SeedableRng;
+use rand_xorshift::XorShiftRng;
+
+fn deadends(level: &GridLevel<Tile>) -> usize {
+    let mut deadends = 0;
+    for y in 0..level.get_height() {
+        for x in 0..level.get_width() {
+            if level.get_tile(x, y).is_ok_and(Tile::is_passable) && is_deadend(level, x, y) {
+                deadends += 1;
+            }
+        }
+    }
+    deadends
+}
+
+fn maze(seed: u64) -> GridLevel<Tile> {
+    let mut level = GridLevel::new_filled_with(Tile::Wall(0), 31, 21);
+    MazeGen::with_seed(1, 1, seed)
+        .with_algorithm(MazeAlgorithm::Kruskal)
+        .generate(&mut level);
+    level
+}
+
+#[test]
+fn full_braid_removes_every_dead_end() {
+    let mut level = maze(1);
+    let before = deadends(&level);
+    assert!(before > 0);
+    let braided = braid_dead_ends(&mut level, 1., &mut XorShiftRng::seed_from_u64(0));
+    assert!(braided > 0 && braided <= before);
+    assert_eq!(deadends(&level), 0);
+}
+
+#[test]
+fn partial_braid_keeps_some_dead_ends() {
+    let mut level = maze(2);
+    let before = deadends(&level);
+    let mut rng = XorShiftRng::seed_from_u64(0);
+    assert_eq!(braid_dead_ends(&mut level.clone(), 0., &mut rng), 0);
+
+    braid_dead_ends(&mut level, 0.5, &mut rng);
+    let after = deadends(&level);
+    assert!(
+        after > 0 && after <= before / 2 + 1,
+        "{} of {}",
+        after,
+        before
+    );
+}
+
+#[test]
+fn braided_mazes_only_gain_floors() {
+    let perfect = maze(3);
+    let mut braided = GridLevel::new_filled_with(Tile::Wall(0), 31, 21);
+    MazeGen::with_seed(1, 1, 3)
+        .with_algorithm(MazeAlgorithm::Kruskal)
+        .with_braid(1.)
+        .generate(&mut braided);
+    assert_eq!(deadends(&braided), 0);
+    for y in 0..21 {
+        for x in 0..31 {
+            if let Ok(&Tile::Floor(_)) = perfect.get_tile(x, y) {
+                assert_eq!(braided.get_tile(x, y).ok(), Some(&Tile::Floor(0)));
+            }
+        }
+    }
+}
@@ -0,0 +1,55 @@
+extern crate dungenon;
+
+mod common;
+
+use dungenon::{generator::BspGen, level::GridLevel, tile::Tile};
+
+use common::reachable;
+
+#[test]
+fn every_room_is_separate_and_reachable() {
+    for seed in 0..5 {
+        let mut level = GridLevel::new_filled_with(Tile::Wall(0), 60, 40);
+        let mut gen = BspGen::with_seed(8, seed);
+        gen.generate(&mut level);
+        let rooms = gen.rooms();
+        // 60x40 can always be split into at least 3x2 leaves of size 8.
+        assert!(rooms.len() >= 6, "only {} rooms", rooms.len());
+        for (i, a) in rooms.iter().enumerate() {
+            assert_eq!(a.id(), i);
+            for b in &rooms[i + 1..] {
+                assert!(!a.overlaps(b, 1), "{:?} and {:?} touch", a, b);
+            }
+        }
+        let start = rooms[0].center();
+        let seen = reachable(&level, (start.x, start.y));
+        for room in rooms {
+            let center = room.center();
+            assert!(seen.contains(&(center.x, center.y)));
+        }
+    }
+}
+
+#[test]
+fn seeded_output_is_reproducible() {
+    let bsp = |seed| {
+        let mut level = GridLevel::new_filled_with(Tile::Wall(0), 50, 50);
+        let mut gen = BspGen::with_seed(6, seed)
+            .with_split_ratio(0.45)
+            .with_room_padding(2)
+            .with_corridor_width(2);
+        gen.generate(&mut level);
+        (level, gen.rooms().to_vec())
+    };
+    assert_eq!(bsp(3), bsp(3));
+}
+
+#[test]
+fn small_levels_get_one_room() {
+    let mut level = GridLevel::new_filled_with(Tile::Wall(0), 5, 4);
+    let mut gen = BspGen::with_seed(10, 1).with_room_padding(3);
+    gen.generate(&mut level);
+    assert_eq!(gen.rooms().len(), 1);
+    let room = &gen.rooms()[0];
+    assert!(room.max().x < 5 && room.max().y < 4);
+}
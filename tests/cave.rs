@@ -0,0 +1,65 @@
+extern crate dungenon;
+
+mod common;
+
+use dungenon::{
+    generator::{CaveGen, CaveRules},
+    level::GridLevel,
+    tile::Tile,
+    util::Neighbourhood,
+};
+
+use common::reachable;
+
+fn floors(level: &GridLevel<Tile>) -> Vec<(usize, usize)> {
+    let mut floors = Vec::new();
+    for y in 0..level.get_height() {
+        for x in 0..level.get_width() {
+            if let Ok(&Tile::Floor(_)) = level.get_tile(x, y) {
+                floors.push((x, y));
+            }
+        }
+    }
+    floors
+}
+
+#[test]
+fn seeded_caves_are_reproducible_and_enclosed() {
+    let cave = |seed| {
+        let mut level = GridLevel::new_filled_with(Tile::Void(0), 40, 30);
+        CaveGen::with_seed(seed).generate(&mut level);
+        level
+    };
+    let level = cave(7);
+    assert_eq!(level, cave(7));
+    assert!(!floors(&level).is_empty());
+    for (x, y) in floors(&level) {
+        assert!(x > 0 && y > 0 && x < 39 && y < 29);
+    }
+}
+
+#[test]
+fn only_the_largest_cavern_is_kept() {
+    for seed in 0..5 {
+        let mut level = GridLevel::new_filled_with(Tile::Wall(0), 50, 40);
+        CaveGen::with_seed(seed)
+            .with_fill_ratio(0.5)
+            .with_neighbourhood(Neighbourhood::Four)
+            .with_rules(CaveRules::new(&[3, 4], &[2, 3, 4]))
+            .keep_largest_cavern(true)
+            .generate(&mut level);
+        let floors = floors(&level);
+        assert!(!floors.is_empty());
+        assert_eq!(reachable(&level, floors[0]).len(), floors.len());
+    }
+}
+
+#[test]
+fn full_fill_leaves_no_floors() {
+    let mut level = GridLevel::new_filled_with(Tile::Floor(0), 20, 20);
+    CaveGen::with_seed(1)
+        .with_fill_ratio(1.)
+        .with_iterations(10)
+        .generate(&mut level);
+    assert!(floors(&level).is_empty());
+}
@@ -0,0 +1,47 @@
+// Not every test uses every helper.
+#![allow(dead_code)]
+
+use std::collections::VecDeque;
+
+use dungenon::{level::GridLevel, tile::Tile, util::Direction};
+
+/// Builds a level from rows of characters: `.` is a floor, `~` a floor with payload 1, `+` a
+/// door, a space is void and everything else is a wall.
+pub fn level_from(rows: &[&str]) -> GridLevel<Tile> {
+    let mut level = GridLevel::new_filled_with(Tile::Wall(0), rows[0].len(), rows.len());
+    for (y, row) in rows.iter().enumerate() {
+        for (x, c) in row.chars().enumerate() {
+            *level.get_mut_tile(x, y).unwrap() = match c {
+                '.' => Tile::Floor(0),
+                '~' => Tile::Floor(1),
+                '+' => Tile::Door(0),
+                ' ' => Tile::Void(0),
+                _ => Tile::Wall(0),
+            };
+        }
+    }
+    level
+}
+
+/// The passable tiles that can be reached orthogonally from `start`, including `start`.
+pub fn reachable(level: &GridLevel<Tile>, start: (usize, usize)) -> Vec<(usize, usize)> {
+    let width = level.get_width();
+    let mut seen = vec![false; width * level.get_height()];
+    seen[start.0 + start.1 * width] = true;
+    let mut tiles = vec![start];
+    let mut queue: VecDeque<_> = tiles.iter().cloned().collect();
+    while let Some(pos) = queue.pop_front() {
+        for dir in Direction::get_orthogonal_dirs() {
+            if let Some(n) = dir.offset(pos) {
+                if level.get_tile_with_tuple(n).is_ok_and(Tile::is_passable)
+                    && !seen[n.0 + n.1 * width]
+                {
+                    seen[n.0 + n.1 * width] = true;
+                    tiles.push(n);
+                    queue.push_back(n);
+                }
+            }
+        }
+    }
+    tiles
+}
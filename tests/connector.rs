@@ -0,0 +1,64 @@
+extern crate dungenon;
+
+mod common;
+
+use dungenon::{
+    generator::{DungeonGen, MazeGen, RoomConnector, RoomGen},
+    level::GridLevel,
+    tile::Tile,
+};
+
+use common::reachable;
+
+fn dungeon(seed: u64, connector: RoomConnector) -> GridLevel<Tile> {
+    let mut level = GridLevel::new_filled_with(Tile::Wall(0), 61, 41);
+    let mut gen = DungeonGen::with_seed(MazeGen::new(1, 1), RoomGen::new(3, 9, 2, 200), seed)
+        .with_connector(connector);
+    gen.generate(&mut level);
+    level
+}
+
+fn passable_tiles(level: &GridLevel<Tile>) -> Vec<(usize, usize)> {
+    let mut tiles = Vec::new();
+    for y in 0..level.get_height() {
+        for x in 0..level.get_width() {
+            if level.get_tile(x, y).is_ok_and(Tile::is_passable) {
+                tiles.push((x, y));
+            }
+        }
+    }
+    tiles
+}
+
+fn count_doors(level: &GridLevel<Tile>) -> usize {
+    passable_tiles(level)
+        .into_iter()
+        .filter(|&(x, y)| level.get_tile(x, y).ok() == Some(&Tile::Door(0)))
+        .count()
+}
+
+#[test]
+fn every_room_is_reachable() {
+    for seed in 0..20 {
+        let level = dungeon(seed, RoomConnector::default());
+        let tiles = passable_tiles(&level);
+        assert!(count_doors(&level) > 0);
+        assert_eq!(
+            reachable(&level, tiles[0]).len(),
+            tiles.len(),
+            "seed {}",
+            seed
+        );
+    }
+}
+
+#[test]
+fn extra_connections_add_doors() {
+    for seed in 0..5 {
+        let tree = dungeon(seed, RoomConnector::new(1, 0.));
+        let loops = dungeon(seed, RoomConnector::new(3, 0.5));
+        assert!(count_doors(&loops) > count_doors(&tree), "seed {}", seed);
+        let tiles = passable_tiles(&loops);
+        assert_eq!(reachable(&loops, tiles[0]).len(), tiles.len());
+    }
+}
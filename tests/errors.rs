@@ -0,0 +1,36 @@
+extern crate dungenon;
+
+use dungenon::{
+    generator::FactionGen,
+    level::GridLevel,
+    tile::{Faction, Tile},
+    util::Error,
+};
+
+#[test]
+fn out_of_bounds_reports_position_and_size() {
+    let level = GridLevel::new_filled_with(Tile::Wall(0), 4, 3);
+    match level.get_tile(4, 1) {
+        Err(Error::IndexOutOfBounds { pos, size }) => {
+            assert_eq!(pos, (4, 1));
+            assert_eq!(size, (4, 3));
+        }
+        other => panic!("unexpected result {:?}", other),
+    }
+    let message = level.get_tile(1, 3).unwrap_err().to_string();
+    assert!(message.contains("(1, 3)"), "{}", message);
+    assert!(message.contains("4x3"), "{}", message);
+}
+
+#[test]
+fn faction_buffer_size_is_checked() {
+    let mut level = GridLevel::new_filled_with(Faction::Faction(0), 8, 8);
+    let mut buffer = GridLevel::new_filled_with(Faction::Faction(0), 8, 7);
+    match FactionGen::with_seed(1).generate(&mut level, &mut buffer) {
+        Err(Error::SizeMismatch { expected, found }) => {
+            assert_eq!(expected, (8, 8));
+            assert_eq!(found, (8, 7));
+        }
+        other => panic!("unexpected result {:?}", other),
+    }
+}
@@ -13,7 +13,7 @@ fn powers_of_two() {
             .unwrap_or_else(|_| panic!("Should exist"))) = Faction::Faction(2);
         let mut b = a.clone();
         for _ in 0..50 {
-            gen.generate(&mut a, &mut b);
+            gen.generate(&mut a, &mut b).unwrap();
         }
     }
 }
@@ -29,7 +29,7 @@ fn powers_of_two_minus_one() {
             .unwrap_or_else(|_| panic!("Should exist"))) = Faction::Faction(2);
         let mut b = a.clone();
         for _ in 0..50 {
-            gen.generate(&mut a, &mut b);
+            gen.generate(&mut a, &mut b).unwrap();
         }
     }
 }
@@ -46,7 +46,7 @@ fn non_square() {
             .unwrap_or_else(|_| panic!("Should exist"))) = Faction::Faction(2);
         let mut b = a.clone();
         for _ in 0..50 {
-            gen.generate(&mut a, &mut b);
+            gen.generate(&mut a, &mut b).unwrap();
         }
     }
 }
@@ -0,0 +1,109 @@
+extern crate dungenon;
+
+use dungenon::{
+    generator::CaveGen,
+    level::{field_of_view, line, line_of_sight, visible_cells, FovMode, GridLevel},
+    tile::Tile,
+};
+
+fn opaque(tile: &Tile) -> bool {
+    !tile.is_passable()
+}
+
+fn room() -> GridLevel<Tile> {
+    let mut level = GridLevel::new_filled_with(Tile::Floor(0), 11, 11);
+    for i in 0..11 {
+        for &(x, y) in &[(i, 0), (i, 10), (0, i), (10, i)] {
+            *level.get_mut_tile(x, y).unwrap() = Tile::Wall(0);
+        }
+    }
+    level
+}
+
+fn visible(fov: &GridLevel<bool>, x: usize, y: usize) -> bool {
+    *fov.get_tile(x, y).unwrap()
+}
+
+#[test]
+fn walls_cast_shadows() {
+    let mut level = room();
+    let fov = field_of_view(&level, (5, 5), 20, FovMode::Symmetric, opaque);
+    for y in 0..11 {
+        for x in 0..11 {
+            assert!(visible(&fov, x, y), "({}, {})", x, y);
+        }
+    }
+
+    *level.get_mut_tile(5, 4).unwrap() = Tile::Wall(0);
+    let fov = field_of_view(&level, (5, 5), 20, FovMode::Symmetric, opaque);
+    assert!(visible(&fov, 5, 4));
+    assert!(!visible(&fov, 5, 3));
+    assert!(!visible(&fov, 5, 1));
+    assert!(visible(&fov, 3, 1));
+    assert!(!line_of_sight(&level, (5, 5), (5, 1), opaque));
+    assert!(line_of_sight(&level, (5, 5), (3, 1), opaque));
+
+    let cells: Vec<(usize, usize)> =
+        visible_cells(&level, (5, 5), 20, FovMode::Symmetric, opaque).collect();
+    let count = (0..11)
+        .flat_map(|y| (0..11).map(move |x| (x, y)))
+        .filter(|&(x, y)| visible(&fov, x, y))
+        .count();
+    assert_eq!(cells.len(), count);
+    assert!(cells.iter().all(|&(x, y)| visible(&fov, x, y)));
+}
+
+#[test]
+fn radius_limits_the_view() {
+    let level = room();
+    let fov = field_of_view(&level, (5, 5), 3, FovMode::Symmetric, opaque);
+    assert!(visible(&fov, 5, 5));
+    assert!(visible(&fov, 8, 5));
+    assert!(visible(&fov, 7, 7));
+    assert!(!visible(&fov, 9, 5));
+    assert!(!visible(&fov, 8, 8));
+
+    let fov = field_of_view(&level, (5, 5), 0, FovMode::Symmetric, opaque);
+    assert_eq!(fov.get_tile(5, 5).ok(), Some(&true));
+    assert_eq!(fov.get_tile(5, 6).ok(), Some(&false));
+}
+
+#[test]
+fn symmetric_mode_is_symmetric() {
+    let mut level = GridLevel::new_filled_with(Tile::Wall(0), 30, 20);
+    CaveGen::with_seed(4).generate(&mut level);
+    let floors: Vec<(usize, usize)> = (0..20)
+        .flat_map(|y| (0..30).map(move |x| (x, y)))
+        .filter(|&(x, y)| level.get_tile(x, y).is_ok_and(Tile::is_passable))
+        .collect();
+    let views: Vec<GridLevel<bool>> = floors
+        .iter()
+        .map(|&pos| field_of_view(&level, pos, 100, FovMode::Symmetric, opaque))
+        .collect();
+    for (i, &a) in floors.iter().enumerate() {
+        for (j, &b) in floors.iter().enumerate() {
+            assert_eq!(visible(&views[i], b.0, b.1), visible(&views[j], a.0, a.1));
+        }
+    }
+
+    let permissive = field_of_view(&level, floors[0], 100, FovMode::Permissive, opaque);
+    for y in 0..20 {
+        for x in 0..30 {
+            assert!(!visible(&views[0], x, y) || visible(&permissive, x, y));
+        }
+    }
+}
+
+#[test]
+fn bresenham_lines() {
+    assert_eq!(line((2, 2), (2, 2)), vec![(2, 2)]);
+    assert_eq!(line((0, 0), (3, 0)), vec![(0, 0), (1, 0), (2, 0), (3, 0)]);
+    assert_eq!(line((3, 3), (0, 0)), vec![(3, 3), (2, 2), (1, 1), (0, 0)]);
+    let points = line((0, 0), (5, 2));
+    assert_eq!(points.len(), 6);
+    assert_eq!(points[5], (5, 2));
+    for step in points.windows(2) {
+        assert_eq!(step[1].0, step[0].0 + 1);
+        assert!(step[1].1 - step[0].1 <= 1);
+    }
+}
@@ -0,0 +1,66 @@
+extern crate dungenon;
+extern crate rand;
+extern crate rand_xorshift;
+
+use dungenon::{
+    generator::{DungeonGen, FactionGen, GridGenerator, MazeGen, RoomGen},
+    level::GridLevel,
+    tile::{Faction, Tile},
+};
+
+use rand::SeedableRng;
+use rand_xorshift::XorShiftRng;
+
+fn run_all(generators: &mut [Box<dyn GridGenerator<Tile>>], seed: u64) -> GridLevel<Tile> {
+    let mut rng = XorShiftRng::seed_from_u64(seed);
+    let mut level = GridLevel::new_filled_with(Tile::Wall(0), 50, 50);
+    for gen in generators.iter_mut() {
+        gen.generate(&mut level, &mut rng).unwrap();
+    }
+    level
+}
+
+#[test]
+fn generators_through_trait_objects() {
+    let mut chain: Vec<Box<dyn GridGenerator<Tile>>> = vec![
+        Box::new(MazeGen::new(1, 1)),
+        Box::new(RoomGen::new(3, 7, 1, 50)),
+    ];
+    let a = run_all(&mut chain, 5);
+    let mut chain: Vec<Box<dyn GridGenerator<Tile>>> = vec![
+        Box::new(MazeGen::new(1, 1)),
+        Box::new(RoomGen::new(3, 7, 1, 50)),
+    ];
+    let b = run_all(&mut chain, 5);
+    assert_eq!(a, b);
+    assert!(a.get_tile(1, 1).map(|t| *t == Tile::Floor(0)).unwrap_or(false));
+}
+
+#[test]
+fn dungeon_through_trait() {
+    let dungeon = |seed| {
+        let mut gen: Box<dyn GridGenerator<Tile>> =
+            Box::new(DungeonGen::new(MazeGen::new(0, 0), RoomGen::new(3, 7, 1, 50)));
+        let mut rng = XorShiftRng::seed_from_u64(seed);
+        let mut level = GridLevel::new_filled_with(Tile::Wall(0), 40, 30);
+        gen.generate(&mut level, &mut rng).unwrap();
+        level
+    };
+    assert_eq!(dungeon(11), dungeon(11));
+}
+
+#[test]
+fn faction_without_buffer() {
+    let mut gen = FactionGen::new();
+    let mut rng = XorShiftRng::seed_from_u64(1);
+    let mut level = GridLevel::new_filled_with(Faction::Neutral, 16, 16);
+    *level.get_mut_tile(8, 8).unwrap_or_else(|_| panic!("Should exist")) = Faction::Faction(1);
+    for _ in 0..20 {
+        GridGenerator::generate(&mut gen, &mut level, &mut rng).unwrap();
+    }
+    for y in 0..16 {
+        for x in 0..16 {
+            assert_eq!(level.get_tile(x, y).ok(), Some(&Faction::Faction(1)));
+        }
+    }
+}
@@ -0,0 +1,85 @@
+extern crate dungenon;
+
+mod common;
+
+use dungenon::{
+    generator::{MazeAlgorithm, MazeGen},
+    level::GridLevel,
+    tile::Tile,
+};
+
+use common::reachable;
+
+fn algorithms() -> Vec<MazeAlgorithm> {
+    vec![
+        MazeAlgorithm::Prim,
+        MazeAlgorithm::Kruskal,
+        MazeAlgorithm::Wilson,
+        MazeAlgorithm::Eller,
+        MazeAlgorithm::BinaryTree,
+        MazeAlgorithm::GrowingTree(0.),
+        MazeAlgorithm::GrowingTree(0.5),
+        MazeAlgorithm::GrowingTree(1.),
+    ]
+}
+
+fn floors(level: &GridLevel<Tile>) -> usize {
+    let mut floors = 0;
+    for y in 0..level.get_height() {
+        for x in 0..level.get_width() {
+            if let Ok(&Tile::Floor(_)) = level.get_tile(x, y) {
+                floors += 1;
+            }
+        }
+    }
+    floors
+}
+
+#[test]
+fn lattice_mazes_are_perfect() {
+    for algorithm in algorithms() {
+        for seed in 0..3 {
+            let mut level = GridLevel::new_filled_with(Tile::Wall(0), 31, 21);
+            MazeGen::with_seed(1, 1, seed)
+                .with_algorithm(algorithm.clone())
+                .generate(&mut level);
+            // 15x10 cells joined by a spanning tree of 149 passages.
+            assert_eq!(floors(&level), 150 + 149, "{:?}", algorithm);
+            assert_eq!(reachable(&level, (1, 1)).len(), 299, "{:?}", algorithm);
+        }
+    }
+}
+
+#[test]
+fn lattice_mazes_leave_other_tiles_alone() {
+    for algorithm in algorithms() {
+        let mut level = GridLevel::new_filled_with(Tile::Wall(0), 30, 20);
+        for y in 5..12 {
+            for x in 8..16 {
+                *level.get_mut_tile(x, y).unwrap() = Tile::Void(0);
+            }
+        }
+        let mut gen = MazeGen::with_seed(0, 0, 2).with_algorithm(algorithm.clone());
+        gen.generate(&mut level);
+        for y in 5..12 {
+            for x in 8..16 {
+                assert_eq!(level.get_tile(x, y).ok(), Some(&Tile::Void(0)));
+            }
+        }
+        assert_eq!(level.get_tile(0, 0).ok(), Some(&Tile::Floor(0)));
+    }
+}
+
+#[test]
+fn seeded_mazes_are_reproducible() {
+    for algorithm in algorithms() {
+        let maze = |seed| {
+            let mut level = GridLevel::new_filled_with(Tile::Wall(0), 25, 25);
+            MazeGen::with_seed(3, 5, seed)
+                .with_algorithm(algorithm.clone())
+                .generate(&mut level);
+            level
+        };
+        assert_eq!(maze(8), maze(8));
+    }
+}
@@ -0,0 +1,142 @@
+extern crate dungenon;
+
+mod common;
+
+use dungenon::{
+    level::{distance_map, farthest_tile, find_path, GridLevel},
+    tile::Tile,
+    util::Neighbourhood,
+};
+
+use common::level_from;
+
+fn mud_costs_five(tile: &Tile) -> u32 {
+    match *tile {
+        Tile::Floor(1) => 5,
+        _ => 1,
+    }
+}
+
+#[test]
+fn a_star_finds_the_cheapest_path() {
+    let level = level_from(&[
+        "#######", //
+        "#.....#", //
+        "#.###.#", //
+        "#.~~~.#", //
+        "#######", //
+    ]);
+    let (path, cost) = find_path(
+        &level,
+        (1, 3),
+        (5, 3),
+        Neighbourhood::Four,
+        Tile::is_passable,
+        mud_costs_five,
+    )
+    .unwrap();
+    // Around the top instead of through the mud.
+    assert_eq!(cost, 8);
+    assert_eq!(path.len(), 9);
+    assert_eq!(path[0], (1, 3));
+    assert_eq!(path[8], (5, 3));
+    for step in path.windows(2) {
+        let (a, b) = (step[0], step[1]);
+        assert_eq!(a.0.abs_diff(b.0) + a.1.abs_diff(b.1), 1);
+    }
+
+    let (_, cost) = find_path(
+        &level,
+        (1, 3),
+        (5, 3),
+        Neighbourhood::Four,
+        Tile::is_passable,
+        |_| 1,
+    )
+    .unwrap();
+    assert_eq!(cost, 4);
+
+    let (path, cost) = find_path(
+        &level,
+        (1, 1),
+        (2, 3),
+        Neighbourhood::Eight,
+        Tile::is_passable,
+        |_| 1,
+    )
+    .unwrap();
+    assert_eq!((path.len(), cost), (3, 2));
+
+    assert!(find_path(
+        &level,
+        (1, 1),
+        (3, 2),
+        Neighbourhood::Four,
+        Tile::is_passable,
+        |_| 1
+    )
+    .is_none());
+}
+
+#[test]
+fn distance_maps_start_at_the_nearest_source() {
+    let level = level_from(&[
+        "########", //
+        "#......#", //
+        "#.####.#", //
+        "#......#", //
+        "####.###", //
+        "#..#####", //
+        "########", //
+    ]);
+    let distances = distance_map(
+        &level,
+        &[(1, 1), (6, 3)],
+        Neighbourhood::Four,
+        Tile::is_passable,
+        |_| 1,
+    );
+    assert_eq!(distances.get_tile(1, 1).ok(), Some(&Some(0)));
+    assert_eq!(distances.get_tile(6, 3).ok(), Some(&Some(0)));
+    assert_eq!(distances.get_tile(6, 1).ok(), Some(&Some(2)));
+    assert_eq!(distances.get_tile(4, 4).ok(), Some(&Some(3)));
+    assert_eq!(distances.get_tile(0, 0).ok(), Some(&None));
+    assert_eq!(distances.get_tile(1, 5).ok(), Some(&None));
+
+    let from_entrance = distance_map(
+        &level,
+        &[(1, 1)],
+        Neighbourhood::Four,
+        Tile::is_passable,
+        |_| 1,
+    );
+    assert_eq!(farthest_tile(&from_entrance), Some(((6, 3), 7)));
+    assert_eq!(farthest_tile(&GridLevel::new_filled_with(None, 3, 3)), None);
+}
+
+#[test]
+fn huge_costs_saturate() {
+    let level = level_from(&[
+        "#####", //
+        "#.~.#", //
+        "#####", //
+    ]);
+    let (path, cost) = find_path(
+        &level,
+        (1, 1),
+        (3, 1),
+        Neighbourhood::Four,
+        Tile::is_passable,
+        |_| u32::MAX,
+    )
+    .unwrap();
+    assert_eq!((path.len(), cost), (3, u32::MAX));
+    let distances = distance_map(
+        &level,
+        &[(1, 1)],
+        Neighbourhood::Four,
+        Tile::is_passable,
+        |_| u32::MAX - 1,
+    );
+    assert_eq!(distances.get_tile(3, 1).ok(), Some(&Some(u32::MAX)));
+}
@@ -0,0 +1,82 @@
+extern crate dungenon;
+extern crate rand;
+extern crate rand_xorshift;
+
+use dungenon::{
+    generator::{MazeGen, Pipeline, RoomGen, StepOutcome},
+    level::{fill_dead_end_tiles, is_deadend, GridLevel},
+    tile::Tile,
+};
+
+use rand::{RngCore, SeedableRng};
+use rand_xorshift::XorShiftRng;
+
+fn no_dead_ends(level: &GridLevel<Tile>) -> Result<(), String> {
+    for y in 0..level.get_height() {
+        for x in 0..level.get_width() {
+            if let Ok(&Tile::Floor(_)) = level.get_tile(x, y) {
+                if is_deadend(level, x, y) {
+                    return Err(format!("dead end at ({}, {})", x, y));
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn repeat_until_stable_removes_all_dead_ends() {
+    let mut pipeline = Pipeline::new()
+        .generator("maze", MazeGen::new(1, 1))
+        .generator("rooms", RoomGen::new(3, 8, 2, 100))
+        .process("fill dead ends", fill_dead_end_tiles)
+        .repeat_until_stable(10_000)
+        .validate("no dead ends", no_dead_ends);
+    let mut level = GridLevel::new_filled_with(Tile::Wall(0), 40, 40);
+    let report = pipeline.run(&mut level, &mut XorShiftRng::seed_from_u64(3));
+
+    assert!(report.succeeded());
+    assert_eq!(report.steps.len(), 4);
+    assert!(report.steps[0].changed_tiles > 0);
+    assert!(report.steps[2].runs > 1);
+    assert_eq!(no_dead_ends(&level), Ok(()));
+}
+
+#[test]
+fn failed_validation_stops_the_pipeline() {
+    let mut pipeline = Pipeline::new()
+        .generator("maze", MazeGen::new(1, 1))
+        .validate("no dead ends", no_dead_ends)
+        .process("fill dead ends", fill_dead_end_tiles);
+    let mut level = GridLevel::new_filled_with(Tile::Wall(0), 20, 20);
+    let report = pipeline.run(&mut level, &mut XorShiftRng::seed_from_u64(3));
+
+    assert!(!report.succeeded());
+    assert_eq!(report.steps.len(), 2);
+    assert!(report.steps[1].failed());
+}
+
+#[test]
+fn conditional_steps_and_closures() {
+    let mut pipeline = Pipeline::new()
+        .generator(
+            "mark corner",
+            |level: &mut GridLevel<Tile>, rng: &mut dyn RngCore| {
+                *level.get_mut_tile(0, 0).unwrap_or_else(|_| panic!("Should exist")) =
+                    Tile::Floor(rng.next_u32() as usize % 4);
+                Ok(())
+            },
+        )
+        .process("wide only", |level: &mut GridLevel<Tile>| {
+            level.fill_with(Tile::Void(0));
+            true
+        })
+        .only_if(|level: &GridLevel<Tile>| level.get_width() > 10);
+    let mut level = GridLevel::new_filled_with(Tile::Wall(0), 5, 5);
+    let report = pipeline.run(&mut level, &mut XorShiftRng::seed_from_u64(3));
+
+    assert_eq!(report.steps[0].changed_tiles, 1);
+    assert_eq!(report.steps[1].outcome, StepOutcome::Skipped);
+    assert_eq!(report.steps[1].runs, 0);
+    assert_eq!(report.changed_tiles(), 1);
+}
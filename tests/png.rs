@@ -0,0 +1,97 @@
+#![cfg(feature = "image")]
+
+extern crate dungenon;
+
+use std::io::Cursor;
+
+use dungenon::{
+    generator::CaveGen,
+    level::{DefaultPalette, GridLevel, Palette, PngLayout},
+    tile::{Faction, Tile},
+    util::Error,
+};
+
+fn export<T, P: Palette<T>>(layout: &PngLayout, level: &GridLevel<T>, palette: &P) -> Vec<u8> {
+    let mut data = Vec::new();
+    layout.write(level, palette, &mut data).unwrap();
+    data
+}
+
+#[test]
+fn tile_levels_round_trip() {
+    let mut level = GridLevel::new_filled_with(Tile::Wall(0), 30, 20);
+    CaveGen::with_seed(4).generate(&mut level);
+    *level.get_mut_tile(1, 1).unwrap() = Tile::Door(0);
+    *level.get_mut_tile(2, 1).unwrap() = Tile::StairsUp(0);
+
+    for layout in &[
+        PngLayout::new(),
+        PngLayout::new().with_scale(4),
+        PngLayout::new()
+            .with_scale(2)
+            .with_grid_lines([255, 0, 255]),
+    ] {
+        let data = export(layout, &level, &DefaultPalette);
+        let back = layout
+            .read(Cursor::new(data), DefaultPalette::tile_for)
+            .unwrap();
+        assert_eq!(back, level);
+    }
+}
+
+#[test]
+fn grid_lines_and_scaling() {
+    let level = GridLevel::new_filled_with(Tile::Floor(0), 3, 2);
+    let layout = PngLayout::new().with_scale(3).with_grid_lines([255, 0, 0]);
+    let data = export(&layout, &level, &DefaultPalette);
+    // The image is 10x7 pixels, read back as 1x1 tiles it's all grid lines and floor.
+    let pixels = PngLayout::new().read(Cursor::new(data), Some).unwrap();
+    assert_eq!((pixels.get_width(), pixels.get_height()), (10, 7));
+    assert_eq!(pixels.get_tile(0, 0).ok(), Some(&[255, 0, 0]));
+    assert_eq!(pixels.get_tile(9, 6).ok(), Some(&[255, 0, 0]));
+    assert_eq!(pixels.get_tile(3, 4).ok(), Some(&[255, 0, 0]));
+    assert_eq!(pixels.get_tile(4, 4).ok(), Some(&[200, 200, 200]));
+
+    match PngLayout::new().with_grid_lines([0, 0, 0]).write(
+        &level,
+        &DefaultPalette,
+        &mut Vec::new(),
+    ) {
+        Err(Error::InvalidConfig(_)) => {}
+        other => panic!("expected an error, got {:?}", other),
+    }
+}
+
+#[test]
+fn factions_and_custom_palettes() {
+    let mut level = GridLevel::new_filled_with(Faction::Neutral, 4, 4);
+    for i in 0..4 {
+        *level.get_mut_tile(i, i).unwrap() = Faction::Faction(i);
+    }
+    let palette = |faction: &Faction| match *faction {
+        Faction::Faction(index) => [index as u8, 0, 0],
+        _ => [255, 255, 255],
+    };
+    let data = export(&PngLayout::new(), &level, &palette);
+    let back = PngLayout::new()
+        .read(Cursor::new(data), |color| match color {
+            [255, 255, 255] => Some(Faction::Neutral),
+            [index, 0, 0] => Some(Faction::Faction(index as usize)),
+            _ => None,
+        })
+        .unwrap();
+    assert_eq!(back, level);
+
+    let colors: Vec<[u8; 3]> = (0..4)
+        .map(|i| DefaultPalette.color(&Faction::Faction(i)))
+        .collect();
+    for (i, a) in colors.iter().enumerate() {
+        assert!(colors[i + 1..].iter().all(|b| a != b));
+    }
+
+    let data = export(&PngLayout::new(), &level, &DefaultPalette);
+    match PngLayout::new().read(Cursor::new(data), DefaultPalette::tile_for) {
+        Err(Error::Format(_)) => {}
+        other => panic!("expected an error, got {:?}", other),
+    }
+}
@@ -0,0 +1,160 @@
+extern crate dungenon;
+
+use dungenon::{
+    generator::{Prefab, PrefabGen, Room, RoomGen},
+    level::{CharMap, GridLevel},
+    tile::Tile,
+    util::Error,
+};
+
+const SHRINE: &str = r"
+    ?###?
+    #...#
+    #...+
+    #####
+";
+
+#[test]
+fn templates_are_parsed() {
+    let prefab = Prefab::from_ascii(SHRINE).unwrap();
+    assert_eq!((prefab.width(), prefab.height()), (5, 4));
+    assert_eq!(prefab.get(0, 0), None);
+    assert_eq!(prefab.get(1, 0), Some(&Tile::Wall(0)));
+    assert_eq!(prefab.get(2, 1), Some(&Tile::Floor(0)));
+    assert_eq!(prefab.get(4, 2), Some(&Tile::Door(0)));
+    assert_eq!(prefab.get(5, 0), None);
+
+    match Prefab::from_ascii("###\n##\n") {
+        Err(Error::Format(_)) => {}
+        other => panic!("ragged template gave {:?}", other),
+    }
+    match Prefab::from_ascii("#x#") {
+        Err(Error::Format(_)) => {}
+        other => panic!("unknown character gave {:?}", other),
+    }
+    assert!(Prefab::from_ascii("\n  \n").is_err());
+}
+
+#[test]
+fn orientations() {
+    let prefab = Prefab::from_ascii(SHRINE).unwrap();
+    let rotated = prefab.rotated();
+    assert_eq!((rotated.width(), rotated.height()), (4, 5));
+    // The door on the right edge ends up on the bottom edge.
+    assert_eq!(rotated.get(1, 4), Some(&Tile::Door(0)));
+    assert_eq!(rotated.rotated().rotated().rotated(), prefab);
+    assert_eq!(prefab.mirrored().get(0, 2), Some(&Tile::Door(0)));
+    assert_eq!(prefab.mirrored().mirrored(), prefab);
+
+    assert_eq!(prefab.variants(false, false), vec![prefab.clone()]);
+    assert_eq!(prefab.variants(true, true).len(), 8);
+    assert_eq!(prefab.variants(true, true)[0], prefab);
+    let square = Prefab::from_ascii("###\n#.#\n###").unwrap();
+    assert_eq!(square.variants(true, true).len(), 1);
+}
+
+#[test]
+fn stamping_keeps_dont_care_cells() {
+    let prefab = Prefab::from_ascii(SHRINE).unwrap();
+    let mut level = GridLevel::new_filled_with(Tile::Floor(3), 8, 8);
+    prefab.stamp(&mut level, 2, 3).unwrap();
+    assert_eq!(level.get_tile(2, 3).ok(), Some(&Tile::Floor(3)));
+    assert_eq!(level.get_tile(3, 3).ok(), Some(&Tile::Wall(0)));
+    assert_eq!(level.get_tile(6, 5).ok(), Some(&Tile::Door(0)));
+
+    let before = level.clone();
+    assert!(prefab.stamp(&mut level, 4, 4).is_err());
+    match prefab.stamp(&mut level, usize::MAX - 1, 0) {
+        Err(Error::IndexOutOfBounds { .. }) => {}
+        other => panic!("expected an error, got {:?}", other),
+    }
+    assert_eq!(level, before);
+}
+
+#[test]
+fn templates_use_char_maps() {
+    let map = CharMap::default().with_digits(Tile::Floor);
+    let prefab = Prefab::from_ascii_with("#2\n*.", &map, '*').unwrap();
+    assert_eq!(prefab.get(1, 0), Some(&Tile::Floor(2)));
+    assert_eq!(prefab.get(0, 1), None);
+    assert_eq!(prefab.get(1, 1), Some(&Tile::Floor(0)));
+    assert!(Prefab::from_ascii_with("#?", &map, '*').is_err());
+}
+
+#[test]
+fn indents_are_counted_in_characters() {
+    // A wide space is three bytes long, so a byte count would cut it apart.
+    assert!(Prefab::from_ascii("\u{3000}#.\n  #.").is_err());
+    let prefab = Prefab::from_ascii("\u{3000}#.\n\u{3000}.#").unwrap();
+    assert_eq!((prefab.width(), prefab.height()), (2, 2));
+}
+
+fn corridor_next_to(level: &GridLevel<Tile>, area: &Room, others: &[&Room]) -> bool {
+    let (min, max) = (area.min(), area.max());
+    let mut around = Vec::new();
+    for x in min.x..=max.x {
+        around.push((x, min.y.wrapping_sub(1)));
+        around.push((x, max.y + 1));
+    }
+    for y in min.y..=max.y {
+        around.push((min.x.wrapping_sub(1), y));
+        around.push((max.x + 1, y));
+    }
+    around.into_iter().any(|(x, y)| {
+        level.get_tile(x, y).is_ok_and(Tile::is_passable)
+            && !others.iter().any(|room| room.contains(x, y))
+    })
+}
+
+#[test]
+fn placement_rules() {
+    let prefab = Prefab::from_ascii(SHRINE).unwrap();
+    let mut level = GridLevel::new_filled_with(Tile::Wall(0), 40, 30);
+    let mut rooms = RoomGen::with_seed(3, 6, 1, 100, 4);
+    rooms.generate(&mut level);
+    for x in 0..40 {
+        *level.get_mut_tile(x, 15).unwrap() = Tile::Floor(0);
+    }
+
+    let mut gen = PrefabGen::with_seed(vec![prefab], 2)
+        .with_orientations(true, true)
+        .with_max_count(3)
+        .with_attempts(2000)
+        .touching_corridors(true)
+        .avoiding_rooms(rooms.rooms());
+    gen.generate(&mut level);
+
+    let placed = gen.placed();
+    assert!(!placed.is_empty() && placed.len() <= 3);
+    for (i, footprint) in placed.iter().enumerate() {
+        assert_eq!(footprint.id(), i);
+        for room in rooms.rooms().iter().chain(&placed[i + 1..]) {
+            assert!(!footprint.overlaps(room, 0));
+        }
+        let others: Vec<&Room> = rooms.rooms().iter().chain(placed).collect();
+        assert!(corridor_next_to(&level, footprint, &others));
+    }
+}
+
+#[test]
+fn placed_prefabs_are_reserved_for_rooms() {
+    let prefab = Prefab::from_ascii(SHRINE).unwrap();
+    let mut level = GridLevel::new_filled_with(Tile::Wall(0), 40, 30);
+    let mut gen = PrefabGen::with_seed(vec![prefab], 9).with_max_count(2);
+    gen.generate(&mut level);
+    assert_eq!(gen.placed().len(), 2);
+
+    let mut rooms = RoomGen::with_seed(3, 8, 1, 500, 9);
+    gen.reserve(&mut rooms);
+    assert_eq!(rooms.reserved(), gen.placed());
+    rooms.generate(&mut level);
+    assert!(!rooms.rooms().is_empty());
+    for footprint in gen.placed() {
+        for room in rooms.rooms() {
+            assert!(!footprint.overlaps(room, 1));
+        }
+        let (min, max) = (footprint.min(), footprint.max());
+        assert_eq!(level.get_tile(min.x, max.y).ok(), Some(&Tile::Wall(0)));
+        assert_eq!(level.get_tile(max.x, min.y + 2).ok(), Some(&Tile::Door(0)));
+    }
+}
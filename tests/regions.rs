@@ -0,0 +1,112 @@
+extern crate dungenon;
+
+mod common;
+
+use dungenon::{
+    generator::CaveGen,
+    level::{
+        connect_regions, flood_fill, is_connected, label_regions, remove_small_regions, GridLevel,
+        RegionId,
+    },
+    tile::Tile,
+    util::Neighbourhood,
+};
+
+use common::level_from;
+
+#[test]
+fn regions_are_labeled() {
+    let level = level_from(&[
+        "#######", //
+        "#..#..#", //
+        "#..#.##", //
+        "###.###", //
+        "#######", //
+    ]);
+    let regions = label_regions(&level, Neighbourhood::Four, Tile::is_passable);
+    assert_eq!(regions.len(), 3);
+    assert_eq!(
+        regions.labels().get_tile(0, 0).ok(),
+        Some(&RegionId::Impassable)
+    );
+    assert_eq!(
+        regions.labels().get_tile(1, 1).ok(),
+        Some(&RegionId::Region(0))
+    );
+    assert_eq!(
+        regions.labels().get_tile(4, 2).ok(),
+        Some(&RegionId::Region(1))
+    );
+    assert_eq!(
+        regions.labels().get_tile(3, 3).ok(),
+        Some(&RegionId::Region(2))
+    );
+
+    let sizes: Vec<usize> = regions.regions().iter().map(|r| r.size()).collect();
+    assert_eq!(sizes, vec![4, 3, 1]);
+    let second = regions.region_at(5, 1).unwrap();
+    assert_eq!((second.min().x, second.min().y), (4, 1));
+    assert_eq!((second.max().x, second.max().y), (5, 2));
+    assert!(regions.connected((4, 1), (4, 2)));
+    assert!(!regions.connected((1, 1), (4, 1)));
+    assert!(!regions.connected((0, 0), (0, 0)));
+
+    // Diagonal steps join the lone tile to both rooms.
+    assert_eq!(
+        label_regions(&level, Neighbourhood::Eight, Tile::is_passable).len(),
+        1
+    );
+    assert_eq!(
+        flood_fill(&level, (1, 1), Neighbourhood::Eight, Tile::is_passable).len(),
+        8
+    );
+    assert!(flood_fill(&level, (0, 0), Neighbourhood::Four, Tile::is_passable).is_empty());
+    assert!(!is_connected(&level));
+}
+
+#[test]
+fn small_regions_are_removed() {
+    let mut level = level_from(&[
+        "#######", //
+        "#..#..#", //
+        "#..#.##", //
+        "###.###", //
+        "#######", //
+    ]);
+    assert_eq!(remove_small_regions(&mut level, 4, Neighbourhood::Four), 2);
+    assert_eq!(level.get_tile(3, 3).ok(), Some(&Tile::Wall(0)));
+    assert_eq!(level.get_tile(4, 1).ok(), Some(&Tile::Wall(0)));
+    assert_eq!(level.get_tile(1, 1).ok(), Some(&Tile::Floor(0)));
+    assert!(is_connected(&level));
+}
+
+#[test]
+fn regions_get_connected() {
+    let mut level = level_from(&[
+        "#########", //
+        "#..######", //
+        "#..######", //
+        "#######.#", //
+        "#.#######", //
+        "#########", //
+    ]);
+    assert_eq!(connect_regions(&mut level), 2);
+    assert!(is_connected(&level));
+    let floors = |level: &GridLevel<Tile>| {
+        (0..level.get_height())
+            .flat_map(|y| (0..level.get_width()).map(move |x| (x, y)))
+            .filter(|&(x, y)| level.get_tile(x, y).is_ok_and(Tile::is_passable))
+            .count()
+    };
+    // Two tiles down to (1, 4), five to the right and one down to (7, 3).
+    assert_eq!(floors(&level), 6 + 1 + 5);
+    for x in 0..level.get_width() {
+        assert_eq!(level.get_tile(x, 0).ok(), Some(&Tile::Wall(0)));
+    }
+
+    let mut cave = GridLevel::new_filled_with(Tile::Wall(0), 60, 40);
+    CaveGen::with_seed(5).generate(&mut cave);
+    assert!(!is_connected(&cave));
+    connect_regions(&mut cave);
+    assert!(is_connected(&cave));
+}
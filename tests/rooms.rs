@@ -0,0 +1,49 @@
+extern crate dungenon;
+
+use dungenon::{
+    generator::{DungeonGen, MazeGen, RoomGen},
+    level::GridLevel,
+    tile::Tile,
+};
+
+#[test]
+fn rooms_match_the_carved_tiles() {
+    let mut level = GridLevel::new_filled_with(Tile::Wall(0), 50, 40);
+    let mut gen = RoomGen::with_seed(3, 8, 1, 200, 12);
+    gen.generate(&mut level);
+
+    let rooms = gen.rooms();
+    assert!(!rooms.is_empty());
+    for (i, room) in rooms.iter().enumerate() {
+        assert_eq!(room.id(), i);
+        assert_eq!(room.area(), room.width() * room.height());
+        assert!(room.max().x < level.get_width() && room.max().y < level.get_height());
+        assert!(room.contains(room.center().x, room.center().y));
+        for y in room.min().y..=room.max().y {
+            for x in room.min().x..=room.max().x {
+                assert_eq!(level.get_tile(x, y).ok(), Some(&Tile::Floor(0)));
+            }
+        }
+        for other in &rooms[i + 1..] {
+            assert!(!room.overlaps(other, 0));
+        }
+    }
+    let floors = (0..level.get_height())
+        .flat_map(|y| (0..level.get_width()).map(move |x| (x, y)))
+        .filter(|&(x, y)| level.get_tile(x, y).ok() == Some(&Tile::Floor(0)))
+        .count();
+    assert_eq!(floors, rooms.iter().map(|room| room.area()).sum::<usize>());
+}
+
+#[test]
+fn dungeon_exposes_its_rooms() {
+    let mut level = GridLevel::new_filled_with(Tile::Wall(0), 50, 40);
+    let mut gen = DungeonGen::with_seed(MazeGen::new(1, 1), RoomGen::new(3, 8, 2, 200), 12);
+    gen.generate(&mut level);
+
+    assert!(!gen.rooms().is_empty());
+    for room in gen.rooms() {
+        let center = room.center();
+        assert!(level.get_tile(center.x, center.y).is_ok_and(Tile::is_passable));
+    }
+}
@@ -0,0 +1,52 @@
+extern crate dungenon;
+
+use dungenon::{
+    generator::{DungeonGen, FactionGen, MazeGen, RoomGen},
+    level::GridLevel,
+    tile::{Faction, Tile},
+};
+
+fn dungeon(seed: u64) -> GridLevel<Tile> {
+    let mut level = GridLevel::new_filled_with(Tile::Wall(0), 64, 48);
+    let mut gen = DungeonGen::with_seed(MazeGen::new(1, 1), RoomGen::new(3, 8, 2, 100), seed);
+    gen.generate(&mut level);
+    level
+}
+
+#[test]
+fn same_seed_same_dungeon() {
+    assert_eq!(dungeon(42), dungeon(42));
+    assert_eq!(dungeon(7), dungeon(7));
+}
+
+#[test]
+fn different_seeds_different_dungeons() {
+    assert_ne!(dungeon(1), dungeon(2));
+}
+
+#[test]
+fn same_seed_same_maze() {
+    let maze = |seed| {
+        let mut level = GridLevel::new_filled_with(Tile::Wall(0), 40, 40);
+        MazeGen::with_seed(0, 0, seed).generate(&mut level);
+        level
+    };
+    assert_eq!(maze(3), maze(3));
+    assert_ne!(maze(3), maze(4));
+}
+
+#[test]
+fn same_seed_same_factions() {
+    let factions = |seed| {
+        let mut gen = FactionGen::with_seed(seed);
+        let mut a = GridLevel::new(32, 32);
+        *a.get_mut_tile(0, 0).unwrap_or_else(|_| panic!("Should exist")) = Faction::Faction(1);
+        *a.get_mut_tile(31, 31).unwrap_or_else(|_| panic!("Should exist")) = Faction::Faction(2);
+        let mut b = a.clone();
+        for _ in 0..40 {
+            gen.generate(&mut a, &mut b).unwrap();
+        }
+        a
+    };
+    assert_eq!(factions(9), factions(9));
+}
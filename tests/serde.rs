@@ -0,0 +1,101 @@
+#![cfg(feature = "serde")]
+
+extern crate dungenon;
+extern crate serde;
+extern crate serde_json;
+
+use dungenon::{
+    generator::CaveGen,
+    level::{GridLevel, Hitbox, Object, UnalignedLevel},
+    tile::{Faction, Tile},
+    util::Grid,
+};
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize)]
+struct Save {
+    #[serde(with = "dungenon::level::run_length")]
+    level: GridLevel<Tile>,
+}
+
+#[derive(Serialize)]
+struct Bytes {
+    #[serde(with = "dungenon::level::run_length")]
+    level: GridLevel<u8>,
+}
+
+#[test]
+fn levels_round_trip() {
+    let mut level = GridLevel::new_filled_with(Tile::Wall(0), 30, 20);
+    CaveGen::with_seed(1).generate(&mut level);
+    *level.get_mut_tile(3, 4).unwrap() = Tile::Door(7);
+
+    let json = serde_json::to_string(&level).unwrap();
+    assert!(json.starts_with(r#"{"width":30,"data":["#));
+    assert_eq!(
+        serde_json::from_str::<GridLevel<Tile>>(&json).unwrap(),
+        level
+    );
+
+    let save = Save {
+        level: level.clone(),
+    };
+    let compact = serde_json::to_string(&save).unwrap();
+    assert!(compact.len() < json.len());
+    assert_eq!(serde_json::from_str::<Save>(&compact).unwrap().level, level);
+
+    let factions: GridLevel<Faction> = GridLevel::new_filled_with(Faction::Faction(2), 4, 3);
+    let json = serde_json::to_string(&factions).unwrap();
+    assert_eq!(
+        serde_json::from_str::<GridLevel<Faction>>(&json).unwrap(),
+        factions
+    );
+}
+
+#[test]
+fn malformed_grids_are_rejected() {
+    let grid: Grid<u8> = serde_json::from_str(r#"{"width":2,"data":[1,2,3,4]}"#).unwrap();
+    assert_eq!((grid.get_width(), grid.get_height()), (2, 2));
+    assert!(serde_json::from_str::<Grid<u8>>(r#"{"width":2,"data":[1,2,3]}"#).is_err());
+    assert!(serde_json::from_str::<Grid<u8>>(r#"{"width":0,"data":[1]}"#).is_err());
+    assert!(serde_json::from_str::<Save>(r#"{"level":{"width":3,"runs":[[2,"Void"]]}}"#).is_err());
+    assert!(serde_json::from_str::<Grid<u8>>(r#"{"width":0,"data":[]}"#).is_err());
+    assert!(serde_json::from_str::<Save>(
+        r#"{"level":{"width":1,"runs":[[18446744073709551615,"Void"]]}}"#
+    )
+    .is_err());
+    assert!(serde_json::from_str::<Save>(
+        r#"{"level":{"width":1,"runs":[[16777216,"Void"],[1,"Void"]]}}"#
+    )
+    .is_err());
+}
+
+#[test]
+fn oversized_levels_are_not_serialized() {
+    let side = 1 << 12;
+    let fits = Bytes {
+        level: GridLevel::new_filled_with(0, side, side),
+    };
+    assert!(serde_json::to_string(&fits).is_ok());
+    let too_large = Bytes {
+        level: GridLevel::new_filled_with(0, side + 1, side),
+    };
+    assert!(serde_json::to_string(&too_large).is_err());
+}
+
+#[test]
+fn unaligned_levels_round_trip() {
+    let mut level = UnalignedLevel::new();
+    level.add_unchecked(Object::new(
+        Faction::Neutral,
+        Hitbox::Circle([1., 2.].into(), 0.5),
+    ));
+    level.add_unchecked(Object::new(
+        Faction::Void,
+        Hitbox::Aabb([5., 5.].into(), [2., 1.].into()),
+    ));
+    let json = serde_json::to_string(&level).unwrap();
+    let back: UnalignedLevel<Faction> = serde_json::from_str(&json).unwrap();
+    assert_eq!(serde_json::to_string(&back).unwrap(), json);
+    assert!(json.contains(r#"{"Circle":[[1.0,2.0],0.5]}"#));
+}
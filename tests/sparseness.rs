@@ -0,0 +1,92 @@
+extern crate dungenon;
+
+use dungenon::{
+    generator::{DungeonGen, MazeGen, Room, RoomGen, Sparseness},
+    level::{is_deadend, GridLevel},
+    tile::Tile,
+};
+
+fn dungeon(sparseness: Sparseness, loop_chance: f64) -> GridLevel<Tile> {
+    dungeon_and_rooms(sparseness, loop_chance).0
+}
+
+fn dungeon_and_rooms(sparseness: Sparseness, loop_chance: f64) -> (GridLevel<Tile>, Vec<Room>) {
+    let mut level = GridLevel::new_filled_with(Tile::Wall(0), 61, 41);
+    let mut gen = DungeonGen::with_seed(MazeGen::new(1, 1), RoomGen::new(3, 9, 2, 200), 8)
+        .with_sparseness(sparseness)
+        .with_loop_chance(loop_chance);
+    gen.generate(&mut level);
+    (level, gen.rooms().to_vec())
+}
+
+fn count(level: &GridLevel<Tile>, pred: &dyn Fn(usize, usize) -> bool) -> usize {
+    let mut count = 0;
+    for y in 0..level.get_height() {
+        for x in 0..level.get_width() {
+            if pred(x, y) {
+                count += 1;
+            }
+        }
+    }
+    count
+}
+
+fn floors(level: &GridLevel<Tile>) -> usize {
+    count(level, &|x, y| {
+        level.get_tile(x, y).is_ok_and(Tile::is_passable)
+    })
+}
+
+fn dead_ends(level: &GridLevel<Tile>) -> usize {
+    count(level, &|x, y| {
+        level.get_tile(x, y).is_ok_and(Tile::is_passable) && is_deadend(level, x, y)
+    })
+}
+
+#[test]
+fn sparseness_controls_pruning() {
+    let none = dungeon(Sparseness::Passes(0), 0.);
+    let few = dungeon(Sparseness::Passes(3), 0.);
+    let half = dungeon(Sparseness::Fraction(0.5), 0.);
+    let full = dungeon(Sparseness::Full, 0.);
+
+    assert!(floors(&none) > floors(&few));
+    assert!(floors(&few) > floors(&full));
+    assert!(floors(&none) > floors(&half));
+    assert!(floors(&half) > floors(&full));
+    assert!(dead_ends(&none) > 0);
+    assert_eq!(dead_ends(&full), 0);
+}
+
+#[test]
+fn loops_replace_dead_ends() {
+    let pruned = dungeon(Sparseness::Full, 0.);
+    let looped = dungeon(Sparseness::Full, 1.);
+    assert!(floors(&looped) > floors(&pruned));
+    assert_eq!(dead_ends(&looped), 0);
+}
+
+#[test]
+fn fractions_only_prune_corridors() {
+    let corridors = |(level, rooms): &(GridLevel<Tile>, Vec<Room>)| {
+        count(level, &|x, y| {
+            matches!(level.get_tile(x, y), Ok(&Tile::Floor(_)))
+                && !rooms.iter().any(|room| room.contains(x, y))
+        })
+    };
+    let others = |(level, rooms): &(GridLevel<Tile>, Vec<Room>)| {
+        count(level, &|x, y| {
+            level.get_tile(x, y).is_ok_and(Tile::is_passable)
+                && (!matches!(level.get_tile(x, y), Ok(&Tile::Floor(_)))
+                    || rooms.iter().any(|room| room.contains(x, y)))
+        })
+    };
+    let none = dungeon_and_rooms(Sparseness::Passes(0), 0.);
+    let half = dungeon_and_rooms(Sparseness::Fraction(0.5), 0.);
+    let before = corridors(&none);
+    assert_eq!(
+        corridors(&half),
+        before - (before as f64 * 0.5).round() as usize
+    );
+    assert_eq!(others(&half), others(&none));
+}
@@ -0,0 +1,114 @@
+extern crate dungenon;
+
+use dungenon::{
+    generator::{DungeonGen, MazeGen, RoomGen, StairDistance, StairPlacer},
+    level::{distance_map, farthest_tile, is_deadend, GridLevel},
+    tile::Tile,
+    util::{Error, Neighbourhood},
+};
+
+fn dungeon(seed: u64) -> (GridLevel<Tile>, DungeonGen) {
+    let mut level = GridLevel::new_filled_with(Tile::Wall(0), 41, 31);
+    let mut gen = DungeonGen::with_seed(MazeGen::new(1, 1), RoomGen::new(3, 7, 2, 200), seed);
+    gen.generate(&mut level);
+    (level, gen)
+}
+
+fn path_length(level: &GridLevel<Tile>, a: (usize, usize), b: (usize, usize)) -> Option<u32> {
+    let distances = distance_map(level, &[a], Neighbourhood::Four, Tile::is_passable, |_| 1);
+    *distances.get_tile(b.0, b.1).unwrap()
+}
+
+#[test]
+fn stairs_are_connected_and_apart() {
+    let (mut level, gen) = dungeon(3);
+    let mut placer = StairPlacer::with_seed(3)
+        .in_different_rooms(gen.rooms())
+        .avoiding_dead_ends(true);
+    placer.generate(&mut level).unwrap();
+
+    let (entrance, exit) = (placer.entrance().unwrap(), placer.exit().unwrap());
+    assert_eq!(
+        level.get_tile(entrance.0, entrance.1).ok(),
+        Some(&Tile::StairsUp(0))
+    );
+    assert_eq!(
+        level.get_tile(exit.0, exit.1).ok(),
+        Some(&Tile::StairsDown(0))
+    );
+    assert!(!is_deadend(&level, entrance.0, entrance.1));
+    assert!(!is_deadend(&level, exit.0, exit.1));
+    let room_of = |(x, y): (usize, usize)| gen.rooms().iter().position(|r| r.contains(x, y));
+    assert!(room_of(entrance).is_none() || room_of(entrance) != room_of(exit));
+
+    // The exit is at least as far away as any other floor tile from the entrance.
+    let distance = path_length(&level, entrance, exit).unwrap();
+    let distances = distance_map(
+        &level,
+        &[entrance],
+        Neighbourhood::Four,
+        Tile::is_passable,
+        |_| 1,
+    );
+    for y in 0..level.get_height() {
+        for x in 0..level.get_width() {
+            if let Ok(&Some(other)) = distances.get_tile(x, y) {
+                if level.get_tile(x, y).ok() == Some(&Tile::Floor(0)) && !is_deadend(&level, x, y) {
+                    assert!(other <= distance);
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn target_distance_is_met() {
+    let (mut level, _) = dungeon(8);
+    let before = level.clone();
+    let mut placer = StairPlacer::with_seed(8)
+        .with_distance(StairDistance::Target(12))
+        .marking_tiles(false);
+    placer.generate(&mut level).unwrap();
+    assert_eq!(level, before);
+    let (entrance, exit) = (placer.entrance().unwrap(), placer.exit().unwrap());
+    assert_eq!(path_length(&level, entrance, exit), Some(12));
+}
+
+#[test]
+fn levels_without_room_for_stairs_are_errors() {
+    let mut level = GridLevel::new_filled_with(Tile::Wall(0), 5, 5);
+    *level.get_mut_tile(2, 2).unwrap() = Tile::Floor(0);
+    let mut placer = StairPlacer::with_seed(1);
+    match placer.generate(&mut level) {
+        Err(Error::GenerationFailed(_)) => {}
+        other => panic!("expected an error, got {:?}", other),
+    }
+    assert_eq!(placer.entrance(), None);
+}
+
+#[test]
+fn farthest_stairs_span_a_perfect_maze() {
+    let mut level = GridLevel::new_filled_with(Tile::Wall(0), 31, 21);
+    MazeGen::with_seed(1, 1, 5).generate(&mut level);
+    let mut longest = 0;
+    for y in 0..21 {
+        for x in 0..31 {
+            if level.get_tile(x, y).unwrap().is_passable() {
+                let distances = distance_map(
+                    &level,
+                    &[(x, y)],
+                    Neighbourhood::Four,
+                    Tile::is_passable,
+                    |_| 1,
+                );
+                if let Some((_, distance)) = farthest_tile(&distances) {
+                    longest = longest.max(distance);
+                }
+            }
+        }
+    }
+    let mut placer = StairPlacer::with_seed(5).with_attempts(1);
+    placer.generate(&mut level).unwrap();
+    let (entrance, exit) = (placer.entrance().unwrap(), placer.exit().unwrap());
+    assert_eq!(path_length(&level, entrance, exit), Some(longest));
+}
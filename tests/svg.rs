@@ -0,0 +1,89 @@
+extern crate dungenon;
+extern crate rand;
+extern crate rand_xorshift;
+
+use dungenon::{
+    generator::generate_forest,
+    level::{Hitbox, Object, SvgWriter, UnalignedLevel},
+};
+use rand::SeedableRng;
+use rand_xorshift::XorShiftRng;
+
+#[test]
+fn objects_are_drawn_in_a_fitted_view_box() {
+    let mut level = UnalignedLevel::new();
+    level.add_unchecked(Object::new("tree", Hitbox::Circle([2., 3.].into(), 1.)));
+    level.add_unchecked(Object::new(
+        "rock",
+        Hitbox::Aabb([6., 4.].into(), [2., 4.].into()),
+    ));
+    let svg = SvgWriter::new()
+        .with_margin(0.5)
+        .to_svg(&level, |value| match *value {
+            "tree" => "fill:green".to_owned(),
+            _ => "fill:gray".to_owned(),
+        });
+    assert!(svg.starts_with("<svg"));
+    assert!(svg.contains(r#"width="70" height="50" viewBox="0.5 1.5 7 5""#));
+    assert!(svg.contains(r#"<circle cx="2" cy="3" r="1" style="fill:green"/>"#));
+    assert!(svg.contains(r#"<rect x="5" y="2" width="2" height="4" style="fill:gray"/>"#));
+    assert!(!svg.contains("<line"));
+    assert!(svg.trim_end().ends_with("</svg>"));
+}
+
+#[test]
+fn grids_and_forests() {
+    let mut level = UnalignedLevel::new();
+    let (mut a, mut b) = (XorShiftRng::seed_from_u64(1), XorShiftRng::seed_from_u64(2));
+    generate_forest(
+        &mut level,
+        30,
+        (0.5, 1.),
+        (&mut a, &mut b),
+        ((0., 0.), (20., 10.)),
+    );
+    assert!(!level.objects().is_empty());
+    let svg = SvgWriter::new()
+        .with_grid(5.)
+        .to_svg(&level, |_| "fill:darkgreen".to_owned());
+    assert_eq!(svg.matches("<circle").count(), level.objects().len());
+    assert!(svg.matches("<line").count() >= 8);
+
+    let empty: UnalignedLevel<String> = UnalignedLevel::new();
+    let svg = SvgWriter::new().to_svg(&empty, |_| String::new());
+    assert!(svg.contains(r#"viewBox="-1 -1 2 2""#));
+}
+
+#[test]
+fn dense_grids_are_left_out() {
+    let mut level = UnalignedLevel::new();
+    level.add_unchecked(Object::new((), Hitbox::Circle([1e8, 1e8].into(), 1.)));
+    let svg = SvgWriter::new()
+        .with_grid(1.)
+        .to_svg(&level, |_| String::new());
+    assert!(svg.matches("<line").count() <= 10);
+
+    let mut level = UnalignedLevel::new();
+    level.add_unchecked(Object::new((), Hitbox::Circle([100., 100.].into(), 1.)));
+    let svg = SvgWriter::new()
+        .with_grid(1e-6)
+        .to_svg(&level, |_| String::new());
+    assert!(!svg.contains("<line"));
+    assert!(svg.contains("<circle"));
+}
+
+#[test]
+fn hitboxes_that_arent_finite_are_left_out() {
+    let mut level = UnalignedLevel::new();
+    level.add_unchecked(Object::new((), Hitbox::Circle([f32::NAN, 0.].into(), 1.)));
+    level.add_unchecked(Object::new(
+        (),
+        Hitbox::Aabb([0., 0.].into(), [f32::INFINITY, 1.].into()),
+    ));
+    level.add_unchecked(Object::new((), Hitbox::Circle([2., 3.].into(), 1.)));
+    let svg = SvgWriter::new().to_svg(&level, |_| String::new());
+    assert!(svg.contains(r#"viewBox="0 1 4 4""#));
+    assert_eq!(svg.matches("<circle").count(), 1);
+    assert!(!svg.contains("<rect"));
+    assert!(!svg.contains("NaN") && !svg.contains("inf"));
+}
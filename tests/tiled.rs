@@ -0,0 +1,114 @@
+extern crate dungenon;
+extern crate serde_json;
+
+use dungenon::{
+    level::{GidMap, GridLevel, Hitbox, Object, TiledMap, UnalignedLevel},
+    tile::{Faction, Tile},
+    util::Error,
+};
+use serde_json::Value;
+
+fn sample_map() -> TiledMap {
+    let mut level = GridLevel::new_filled_with(Tile::Wall(2), 3, 2);
+    *level.get_mut_tile(1, 0).unwrap() = Tile::Floor(1);
+    *level.get_mut_tile(2, 1).unwrap() = Tile::Void(0);
+    let gids = GidMap::new()
+        .with_kind(Tile::Wall, 1)
+        .with_kind(Tile::Floor, 17);
+    let factions = GridLevel::new_filled_with(Faction::Faction(4), 3, 2);
+
+    let mut objects = UnalignedLevel::new();
+    objects.add_unchecked(Object::new(
+        "chest",
+        Hitbox::Aabb([1.5, 0.5].into(), [1., 1.].into()),
+    ));
+    objects.add_unchecked(Object::new(
+        "a \"trap\"",
+        Hitbox::Circle([2., 1.].into(), 0.5),
+    ));
+
+    let mut map = TiledMap::new(3, 2)
+        .with_tile_size(32, 32)
+        .with_tileset(1, "dungeon.tsx");
+    map.add_tile_layer("level", &level, |tile| gids.gid(tile))
+        .unwrap();
+    map.add_tile_layer("factions", &factions, |faction| match *faction {
+        Faction::Faction(index) => 100 + index as u32,
+        _ => 0,
+    })
+    .unwrap();
+    map.add_object_layer("objects", &objects, |name| name.to_string())
+        .unwrap();
+    map
+}
+
+#[test]
+fn json_maps() {
+    let json: Value = serde_json::from_str(&sample_map().to_json()).unwrap();
+    assert_eq!(json["width"], 3);
+    assert_eq!(json["tilewidth"], 32);
+    assert_eq!(json["nextobjectid"], 3);
+    assert_eq!(json["tilesets"][0]["source"], "dungeon.tsx");
+    let layers = json["layers"].as_array().unwrap();
+    assert_eq!(layers.len(), 3);
+    assert_eq!(layers[0]["type"], "tilelayer");
+    assert_eq!(layers[0]["data"], serde_json::json!([3, 18, 3, 3, 3, 0]));
+    assert_eq!(layers[1]["data"][0], 104);
+
+    let objects = layers[2]["objects"].as_array().unwrap();
+    assert_eq!(objects[0]["type"], "chest");
+    assert_eq!(
+        (&objects[0]["x"], &objects[0]["y"], &objects[0]["width"]),
+        (&Value::from(32), &Value::from(0), &Value::from(32))
+    );
+    assert!(objects[0].get("ellipse").is_none());
+    assert_eq!(objects[1]["type"], "a \"trap\"");
+    assert_eq!(objects[1]["ellipse"], true);
+    assert_eq!(objects[1]["id"], 2);
+}
+
+#[test]
+fn tmx_maps() {
+    let tmx = sample_map().to_tmx();
+    assert!(tmx.starts_with("<?xml"));
+    assert!(tmx.contains(r#"<tileset firstgid="1" source="dungeon.tsx"/>"#));
+    assert!(tmx.contains("<data encoding=\"csv\">\n3,18,3,\n3,3,0\n  </data>"));
+    assert!(tmx.contains(r#"<object id="1" type="chest" x="32" y="0" width="32" height="32"/>"#));
+    assert!(tmx.contains(r#"type="a &quot;trap&quot;" x="48" y="16""#));
+    assert!(tmx.contains("<ellipse/>"));
+    assert!(tmx.trim_end().ends_with("</map>"));
+}
+
+#[test]
+fn layers_have_to_fit_the_map() {
+    let mut map = TiledMap::new(3, 2);
+    let level = GridLevel::new_filled_with(Tile::Wall(0), 2, 3);
+    match map.add_tile_layer("level", &level, |_| 1) {
+        Err(Error::SizeMismatch { expected, found }) => {
+            assert_eq!((expected, found), ((3, 2), (2, 3)))
+        }
+        other => panic!("expected an error, got {:?}", other),
+    }
+}
+
+#[test]
+fn gids_and_hitboxes_are_checked() {
+    let gids = GidMap::new().with_kind(Tile::Wall, u32::MAX - 1);
+    assert_eq!(gids.gid(&Tile::Wall(1)), u32::MAX);
+    assert_eq!(gids.gid(&Tile::Wall(2)), 0);
+    assert_eq!(gids.gid(&Tile::Wall(usize::MAX)), 0);
+    assert_eq!(gids.gid(&Tile::Floor(1)), 0);
+
+    let mut objects = UnalignedLevel::new();
+    objects.add_unchecked(Object::new((), Hitbox::Circle([1., 1.].into(), 1.)));
+    objects.add_unchecked(Object::new(
+        (),
+        Hitbox::Aabb([f32::NAN, 1.].into(), [1., 1.].into()),
+    ));
+    let mut map = TiledMap::new(3, 2);
+    match map.add_object_layer("objects", &objects, |_| String::new()) {
+        Err(Error::Format(message)) => assert!(message.contains("object 1"), "{}", message),
+        other => panic!("expected an error, got {:?}", other),
+    }
+    assert!(!map.to_json().contains("objectgroup"));
+}
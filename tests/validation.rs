@@ -0,0 +1,69 @@
+extern crate dungenon;
+
+use dungenon::{
+    generator::{MazeGen, RoomGen},
+    level::GridLevel,
+    tile::Tile,
+    util::Error,
+};
+
+fn message<T>(result: Result<T, Error>) -> String {
+    match result {
+        Err(Error::InvalidConfig(message)) => message,
+        Err(e) => panic!("unexpected error {:?}", e),
+        Ok(_) => panic!("expected an error"),
+    }
+}
+
+#[test]
+fn room_parameters_are_checked() {
+    let level = GridLevel::new_filled_with(Tile::Wall(0), 20, 10);
+    assert!(message(RoomGen::try_new(6, 5, 1, 10, &level)).contains("smaller than"));
+    assert!(message(RoomGen::try_new(3, 11, 1, 10, &level)).contains("20x10"));
+    assert!(message(RoomGen::try_new(3, 5, 1, 0, &level)).contains("attempts"));
+    assert!(RoomGen::try_new(0, 5, 1, 10, &level).is_ok());
+    assert!(RoomGen::try_new(3, 10, 1, 10, &level).is_ok());
+}
+
+#[test]
+fn room_sizes_exclude_the_minimum() {
+    let mut level = GridLevel::new_filled_with(Tile::Wall(0), 20, 20);
+    assert!(message(RoomGen::try_new(4, 4, 1, 50, &level)).contains("smaller than"));
+    let mut gen = RoomGen::try_new(3, 4, 1, 50, &level).unwrap();
+    gen.generate(&mut level);
+    assert!(!gen.rooms().is_empty());
+    for room in gen.rooms() {
+        assert_eq!((room.width(), room.height()), (4, 4));
+    }
+}
+
+#[test]
+fn maze_start_is_checked() {
+    let mut level = GridLevel::new_filled_with(Tile::Wall(0), 10, 10);
+    assert!(message(MazeGen::try_new(10, 3, &level)).contains("outside"));
+    *level.get_mut_tile(2, 2).unwrap() = Tile::Void(0);
+    assert!(message(MazeGen::try_new(2, 2, &level)).contains("Void"));
+    assert!(MazeGen::try_new(3, 3, &level).is_ok());
+}
+
+#[test]
+fn checked_generators_can_be_reseeded() {
+    let empty = GridLevel::new_filled_with(Tile::Wall(0), 30, 20);
+    let run = |rooms: &mut RoomGen, maze: &mut MazeGen| {
+        let mut level = empty.clone();
+        maze.generate(&mut level);
+        rooms.generate(&mut level);
+        level
+    };
+    let mut rooms = RoomGen::try_new(2, 6, 1, 30, &empty).unwrap();
+    let mut maze = MazeGen::try_new(1, 1, &empty).unwrap();
+    rooms.reseed(7);
+    maze.reseed(8);
+    assert_eq!(
+        run(&mut rooms, &mut maze),
+        run(
+            &mut RoomGen::with_seed(2, 6, 1, 30, 7),
+            &mut MazeGen::with_seed(1, 1, 8)
+        )
+    );
+}
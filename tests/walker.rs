@@ -0,0 +1,70 @@
+extern crate dungenon;
+
+use dungenon::{generator::WalkerGen, level::GridLevel, tile::Tile};
+
+fn floor_count(level: &GridLevel<Tile>) -> usize {
+    let mut floors = 0;
+    for y in 0..level.get_height() {
+        for x in 0..level.get_width() {
+            if let Ok(&Tile::Floor(_)) = level.get_tile(x, y) {
+                floors += 1;
+            }
+        }
+    }
+    floors
+}
+
+#[test]
+fn walkers_stop_at_the_target() {
+    let mut level = GridLevel::new_filled_with(Tile::Wall(0), 40, 40);
+    WalkerGen::with_seed(20, 20, 2)
+        .with_walkers(3, 8)
+        .with_steps(1_000_000)
+        .with_target_floor(0.25)
+        .generate(&mut level);
+    assert_eq!(floor_count(&level), 400);
+    for i in 0..40 {
+        for &(x, y) in &[(i, 0), (i, 39), (0, i), (39, i)] {
+            assert_eq!(level.get_tile(x, y).ok(), Some(&Tile::Wall(0)));
+        }
+    }
+}
+
+#[test]
+fn walkers_respect_the_step_budget() {
+    let mut level = GridLevel::new_filled_with(Tile::Wall(0), 40, 40);
+    WalkerGen::with_seed(5, 5, 9)
+        .with_steps(30)
+        .with_target_floor(1.)
+        .with_turn_chance(0.)
+        .generate(&mut level);
+    let floors = floor_count(&level);
+    assert!(floors > 1 && floors <= 31, "{} floors", floors);
+    assert_eq!(level.get_tile(5, 5).ok(), Some(&Tile::Floor(0)));
+}
+
+#[test]
+fn seeded_walks_are_reproducible() {
+    let walk = |seed| {
+        let mut level = GridLevel::new_filled_with(Tile::Wall(0), 30, 20);
+        WalkerGen::with_seed(15, 10, seed)
+            .with_lifecycle(0.2, 0.1)
+            .generate(&mut level);
+        level
+    };
+    assert_eq!(walk(4), walk(4));
+}
+
+#[test]
+fn starts_on_the_border_move_inside() {
+    let mut level = GridLevel::new_filled_with(Tile::Wall(0), 20, 20);
+    WalkerGen::with_seed(0, 19, 3)
+        .with_steps(200)
+        .generate(&mut level);
+    assert_eq!(level.get_tile(1, 18).ok(), Some(&Tile::Floor(0)));
+    for i in 0..20 {
+        for &(x, y) in &[(i, 0), (i, 19), (0, i), (19, i)] {
+            assert_eq!(level.get_tile(x, y).ok(), Some(&Tile::Wall(0)));
+        }
+    }
+}
@@ -0,0 +1,152 @@
+extern crate dungenon;
+
+use dungenon::{
+    generator::{WfcGen, WfcMode},
+    level::GridLevel,
+    tile::Tile,
+    util::Error,
+};
+
+fn sample() -> GridLevel<Tile> {
+    let rows = [
+        "##########",
+        "#...#....#",
+        "#........#",
+        "#...#....#",
+        "##.####.##",
+        "#...#....#",
+        "#........#",
+        "##########",
+    ];
+    let mut level = GridLevel::new_filled_with(Tile::Wall(0), 10, rows.len());
+    for (y, row) in rows.iter().enumerate() {
+        for (x, c) in row.chars().enumerate() {
+            if c == '.' {
+                *level.get_mut_tile(x, y).unwrap() = Tile::Floor(0);
+            }
+        }
+    }
+    level
+}
+
+fn window(level: &GridLevel<Tile>, x: usize, y: usize, n: usize) -> Vec<Tile> {
+    let (width, height) = (level.get_width(), level.get_height());
+    let mut tiles = Vec::new();
+    for dy in 0..n {
+        for dx in 0..n {
+            tiles.push(
+                level
+                    .get_tile((x + dx) % width, (y + dy) % height)
+                    .unwrap()
+                    .clone(),
+            );
+        }
+    }
+    tiles
+}
+
+fn windows(level: &GridLevel<Tile>, n: usize, periodic: bool) -> Vec<Vec<Tile>> {
+    let (width, height) = if periodic {
+        (level.get_width(), level.get_height())
+    } else {
+        (level.get_width() - n + 1, level.get_height() - n + 1)
+    };
+    let mut windows = Vec::new();
+    for y in 0..height {
+        for x in 0..width {
+            windows.push(window(level, x, y, n));
+        }
+    }
+    windows
+}
+
+#[test]
+fn overlapping_output_only_contains_sample_patterns() {
+    let sample = sample();
+    let known = windows(&sample, 3, false);
+    for &periodic in &[false, true] {
+        let mut level = GridLevel::new_filled_with(Tile::Void(0), 24, 16);
+        WfcGen::with_seed(&sample, WfcMode::Overlapping(3), 1)
+            .with_periodic_output(periodic)
+            .generate(&mut level)
+            .unwrap();
+        for pattern in windows(&level, 3, periodic) {
+            assert!(known.contains(&pattern), "{:?}", pattern);
+        }
+    }
+}
+
+#[test]
+fn adjacency_output_only_contains_sample_neighbours() {
+    let sample = sample();
+    let known = windows(&sample, 2, false);
+    let pairs = |windows: &[Vec<Tile>]| {
+        let mut pairs = Vec::new();
+        for w in windows {
+            pairs.push((w[0].clone(), w[1].clone()));
+            pairs.push((w[0].clone(), w[2].clone()));
+        }
+        pairs
+    };
+    let known = pairs(&known);
+    let mut level = GridLevel::new_filled_with(Tile::Void(0), 20, 20);
+    WfcGen::with_seed(&sample, WfcMode::Adjacency, 2)
+        .with_symmetry(8)
+        .generate(&mut level)
+        .unwrap();
+    for pair in pairs(&windows(&level, 2, false)) {
+        assert!(known.contains(&pair), "{:?}", pair);
+    }
+}
+
+#[test]
+fn pinned_tiles_are_kept() {
+    let sample = sample();
+    for seed in 0..3 {
+        let mut level = GridLevel::new_filled_with(Tile::Void(0), 20, 14);
+        WfcGen::with_seed(&sample, WfcMode::Overlapping(2), seed)
+            .with_symmetry(2)
+            .pin(5, 5, Tile::Floor(0))
+            .pin(12, 3, Tile::Wall(0))
+            .generate(&mut level)
+            .unwrap();
+        assert_eq!(level.get_tile(5, 5).ok(), Some(&Tile::Floor(0)));
+        assert_eq!(level.get_tile(12, 3).ok(), Some(&Tile::Wall(0)));
+    }
+}
+
+#[test]
+fn impossible_requests_are_errors() {
+    let sample = sample();
+    let mut level = GridLevel::new_filled_with(Tile::Wall(0), 10, 10);
+    let mut gen = WfcGen::with_seed(&sample, WfcMode::Overlapping(3), 0).pin(3, 3, Tile::Void(0));
+    match gen.generate(&mut level) {
+        Err(Error::GenerationFailed(_)) => {}
+        other => panic!("unexpected result {:?}", other),
+    }
+    let mut gen = WfcGen::with_seed(&sample, WfcMode::Overlapping(3), 0).pin(10, 3, Tile::Wall(0));
+    match gen.generate(&mut level) {
+        Err(Error::InvalidConfig(_)) => {}
+        other => panic!("unexpected result {:?}", other),
+    }
+    let mut gen = WfcGen::with_seed(&sample, WfcMode::Overlapping(20), 0);
+    match gen.generate(&mut level) {
+        Err(Error::InvalidConfig(_)) => {}
+        other => panic!("unexpected result {:?}", other),
+    }
+}
+
+#[test]
+fn seeded_output_is_reproducible() {
+    let sample = sample();
+    let run = |seed| {
+        let mut level = GridLevel::new_filled_with(Tile::Void(0), 16, 16);
+        WfcGen::with_seed(&sample, WfcMode::Overlapping(3), seed)
+            .with_periodic_input(true)
+            .with_symmetry(4)
+            .generate(&mut level)
+            .unwrap();
+        level
+    };
+    assert_eq!(run(6), run(6));
+}